@@ -0,0 +1,250 @@
+use crate :: { import::*, Config, EkkeResult, EncryptedTag, FilePolicy, MergeStrategy, TagResolver, XdgDirs, layer_schema::LayerSchema, source::*, strategy::Strategies, tags::Resolvers };
+
+
+/// Create a [`Config`] from any number of named sources. Sources are merged in the order they
+/// are added, so add the ones with the lowest priority first:
+///
+/// ```no_run
+/// # use ekke_config::{ Config, ConfigBuilder, Layer };
+/// # #[ derive( serde::Serialize, serde::Deserialize, Debug, Clone ) ] struct Settings {}
+/// #
+/// let config: Config<Settings> = ConfigBuilder::new()
+///
+///    .file         ( Layer::DEFAULT, "/usr/share/my_app/defaults.yml"   )
+///    .optional_file( "system"      , "/etc/my_app/config.yml"           )
+///    .optional_dir ( "system.d"    , "/etc/my_app/conf.d"               )
+///    .optional_file( Layer::USERSET, "/home/user/.config/my_app.yml"    )
+///    .env          ( "env"         , "MY_APP_"                          )
+///    .cli          ( "cli"         , vec![ "my_app.log_lvl=trace" ]     )
+///
+///    .build()?;
+///
+/// // The same, but finding system and user files in the standard locations.
+/// //
+/// let config: Config<Settings> = ConfigBuilder::new()
+///
+///    .file( Layer::DEFAULT, "/usr/share/my_app/defaults.yml" )
+///    .xdg ( "my_app"      , "config.yml"                     )
+///    .env ( "env"         , "MY_APP_"                        )
+///
+///    .build()?;
+/// # Ok::<(), failure::Error>(())
+/// ```
+///
+/// Each source becomes a [`Layer`](crate::Layer) of the resulting config. Contrary to the defaults file accepted
+/// by `Config::try_from`, the sources contain just settings, no meta keys like `default` or `userset`.
+///
+/// The accessors [`Config::default`], [`Config::userset`] and [`Config::runtime`] return the layers named
+/// [`Layer::DEFAULT`](crate::Layer::DEFAULT), [`Layer::USERSET`](crate::Layer::USERSET) and
+/// [`Layer::RUNTIME`](crate::Layer::RUNTIME), so use those names where they apply.
+///
+/// Custom providers can be added with [`ConfigBuilder::source`] and custom tags with [`ConfigBuilder::resolver`].
+///
+#[ derive( Debug, Default ) ]
+//
+pub struct ConfigBuilder
+{
+	sources   : Vec< Arc< dyn Source > >,
+	resolvers : Resolvers               ,
+	sensitive : Vec< String >           ,
+	strategies: Strategies              ,
+	policy    : Option< FilePolicy >    ,
+
+	schema    : Option< PathBuf >       ,
+}
+
+
+
+impl ConfigBuilder
+{
+	/// Create a builder without sources.
+	///
+	pub fn new() -> Self
+	{
+		Self::default()
+	}
+
+
+	/// Add a yaml file. Building fails if it does not exist.
+	///
+	pub fn file( self, name: impl Into<String>, path: impl Into<PathBuf> ) -> Self
+	{
+		self.source( FileSource::new( name, path ) )
+	}
+
+
+	/// Add a yaml file which is skipped if it does not exist.
+	///
+	pub fn optional_file( self, name: impl Into<String>, path: impl Into<PathBuf> ) -> Self
+	{
+		self.source( FileSource::optional( name, path ) )
+	}
+
+
+	/// Add the configuration files called `file` for `app` from the XDG base directories. Every
+	/// location where the file might be is added as an optional file, from `/etc/<app>` and `$XDG_CONFIG_DIRS`
+	/// to the user file in `$XDG_CONFIG_HOME`, which is named [`Layer::USERSET`](crate::Layer::USERSET).
+	/// The origin of each layer records the path of the file that was found. See [`XdgDirs`] for the details.
+	///
+	pub fn xdg( mut self, app: &str, file: &str ) -> Self
+	{
+		for ( name, path ) in XdgDirs::new( app ).candidates( file )
+		{
+			self = self.optional_file( name, path );
+		}
+
+		self
+	}
+
+
+	/// Add a directory of yaml files. All files with a `.yml` or `.yaml` extension are merged
+	/// in alphabetical order into a single layer. Building fails if the directory does not exist.
+	///
+	pub fn dir( self, name: impl Into<String>, path: impl Into<PathBuf> ) -> Self
+	{
+		self.source( DirSource::new( name, path ) )
+	}
+
+
+	/// Add a directory of yaml files which is skipped if it does not exist.
+	///
+	pub fn optional_dir( self, name: impl Into<String>, path: impl Into<PathBuf> ) -> Self
+	{
+		self.source( DirSource::optional( name, path ) )
+	}
+
+
+	/// Add a yaml string.
+	///
+	pub fn string( self, name: impl Into<String>, input: impl Into<String> ) -> Self
+	{
+		self.source( StrSource::new( name, input ) )
+	}
+
+
+	/// Add the environment variables starting with `prefix`. The rest of the variable name is
+	/// lowercased and split on double underscores, so with prefix `MY_APP_`, the variable
+	/// `MY_APP_OTHER_COMP__ALGO=euler` sets `/other_comp/algo`. Values are parsed as yaml, so numbers
+	/// and lists work. Anything that's not valid yaml is taken as a string.
+	///
+	pub fn env( self, name: impl Into<String>, prefix: impl Into<String> ) -> Self
+	{
+		self.source( EnvSource::new( name, prefix ) )
+	}
+
+
+	/// Add command line options of the form `other_comp.algo=euler`. Keys are split on dots and
+	/// values are parsed like for [`ConfigBuilder::env`]. Extracting the options from the command
+	/// line is left to the argument parser of your application.
+	///
+	pub fn cli<I, S>( self, name: impl Into<String>, args: I ) -> Self
+
+		where I: IntoIterator<Item=S>, S: Into<String>
+	{
+		self.source( CliSource::new( name, args ) )
+	}
+
+
+	/// Add data that is already in memory.
+	///
+	pub fn value( self, name: impl Into<String>, data: Mapping ) -> Self
+	{
+		self.source( ValueSource::new( name, data ) )
+	}
+
+
+	/// Add a custom source.
+	///
+	pub fn source( mut self, source: impl Source + 'static ) -> Self
+	{
+		self.sources.push( Arc::new( source ) );
+		self
+	}
+
+
+	/// Add a resolver for tagged values. It replaces a resolver for the same tag, so this can also override
+	/// the built in ones. Use `.resolver( CmdTag )` to allow configuration to run commands with `!cmd`.
+	///
+	pub fn resolver( mut self, resolver: impl TagResolver + 'static ) -> Self
+	{
+		self.resolvers.add( Arc::new( resolver ) );
+		self
+	}
+
+
+	/// Decrypt `!encrypted` values with the key in this file. See [`EncryptionKey`](crate::EncryptionKey).
+	///
+	pub fn key_file( self, path: impl Into<PathBuf> ) -> Self
+	{
+		self.resolver( EncryptedTag::from_file( path ) )
+	}
+
+
+	/// Mark the value at a json pointer, and everything below it, as sensitive. See [`Config`] for what that does.
+	///
+	pub fn sensitive( mut self, ptr: impl Into<String> ) -> Self
+	{
+		self.sensitive.push( ptr.into() );
+		self
+	}
+
+
+	/// Set how the sequence at a json pointer is merged with the sequences of the layers below, see [`MergeStrategy`].
+	///
+	pub fn merge_strategy( mut self, ptr: impl Into<String>, strategy: MergeStrategy ) -> Self
+	{
+		self.strategies.insert( ptr.into(), strategy );
+		self
+	}
+
+
+	/// Check the permissions and owner of every file that is read, see [`FilePolicy`]. The config keeps
+	/// the policy for [`Config::reload`].
+	///
+	pub fn file_policy( mut self, policy: FilePolicy ) -> Self
+	{
+		self.policy = Some( policy );
+		self
+	}
+
+
+	/// Check every layer against the JSON Schema in a json or yaml file, see `Config::set_schema`. Building fails when
+	/// the layers break it. Requires the `schema` feature.
+	///
+	#[ cfg( feature = "schema" ) ]
+	//
+	pub fn schema_file( mut self, path: impl Into<PathBuf> ) -> Self
+	{
+		self.schema = Some( path.into() );
+		self
+	}
+
+
+	/// Load all sources and merge them into a Config. Fails if a required source is not present.
+	///
+	pub fn build<T>( self ) -> EkkeResult< Config<T> > where T: Clone + DeserializeOwned + Serialize + Debug
+	{
+		let Self { sources, resolvers, sensitive, strategies, policy, schema } = self;
+
+		let load = ||
+		{
+			let schema = match &schema
+			{
+				Some( path ) => LayerSchema::from_file( path )?,
+				None         => LayerSchema::default()        ,
+			};
+
+			Config::load( sources, resolvers, sensitive, strategies, schema )
+		};
+
+		let mut cfg = match &policy
+		{
+			Some( policy ) => policy.apply( load )?,
+			None           => load()?             ,
+		};
+
+		cfg.set_file_policy( policy );
+
+		Ok( cfg )
+	}
+}
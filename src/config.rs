@@ -1,11 +1,70 @@
-use crate :: { import::*, EkkeResult, EkkeCfgError };
+use crate ::
+{
+	import::*, Change, Document, EkkeResult, EkkeCfgError, EncryptedTag, FilePolicy, Layer, Source, Transaction, Validate, Validator,
+
+	expand       :: { expand_path                                                     } ,
+	history      :: { History, Snapshot                                               } ,
+	interpolate  :: { interpolate, literal                                            } ,
+	layer_schema :: { LayerSchema                                                     } ,
+	lock         :: { check_locks, unlocked_data                                      } ,
+	patch        :: { merge_patch, JsonPatch                                          } ,
+	pointer      :: { unescape                                                        } ,
+	secret       :: { is_sensitive, redact, redact_message, secret_pointers, REDACTED } ,
+	source       :: { nest, parse_file, read_file, load_layer, make_layer, Sources    } ,
+	strategy     :: { merge_with, MergeStrategy, Strategies                           } ,
+	tags         :: { Resolvers                                                       } ,
+	unset        :: { apply_unsets, required                                          } ,
+	yaml         :: { parse_tagged                                                    } ,
+};
 
 
 /// A configuration object that can be created from multiple layers of yaml input. Later
 /// input will merge into the earlier data and override options that are already set.
-/// Objects will be merged recursively. Arrays contents will be replaced.
+/// Objects will be merged recursively. Arrays contents will be replaced, unless a [`MergeStrategy`](crate::MergeStrategy)
+/// is set for them.
+///
+/// The layers are kept in order. The usual ones are `default`, `userset` and `runtime`, but
+/// a [`ConfigBuilder`](crate::ConfigBuilder) can create a config from any number of named layers.
+///
+/// String values can refer to other values with `${...}`. References are resolved after merging all
+/// layers, so when a layer overrides a referenced value, the strings referring to it change as well:
+///
+/// - `${data_dir}`: the key `data_dir` in the same mapping as the string, or in the closest parent mapping that has it
+/// - `${/my_app/data_dir}`: the value at a json pointer
+/// - `${env:HOME}`: an environment variable
+///
+/// When the whole string is a single reference, it is replaced by the referenced value, whatever its
+/// type. Otherwise the referenced values must be strings, numbers or booleans. Write `$${` for a literal `${`.
+/// References to missing keys and reference cycles are reported as [`EkkeCfgError::Interpolation`](crate::EkkeCfgError::Interpolation)
+/// with the pointer of the offending value.
+///
+/// Configuration files can be split up with `!include path`. The node is replaced by the content of the file,
+/// resolved relative to the including file. A path with glob characters, like `!include conf.d/*.yml`, merges
+/// all matching files in alphabetical order. Includes can be nested, but cycles are an error: [`EkkeCfgError::IncludeCycle`](crate::EkkeCfgError::IncludeCycle).
+/// The included files are recorded on the layer, see [`Layer::includes`](crate::Layer::includes) and [`Config::files`].
+///
+/// Values can come from elsewhere with tags, like `token: !file /run/secrets/token` or `host: !env DB_HOST`. These are
+/// resolved when a layer is loaded, before merging. See [`TagResolver`](crate::TagResolver) for the available tags
+/// and how to add your own.
+///
+/// Json pointers can be marked as sensitive, with a `sensitive` meta key in the defaults, [`ConfigBuilder::sensitive`](crate::ConfigBuilder::sensitive)
+/// or [`Config::add_sensitive`]. The values at these pointers, and everything below them, are shown as `***` in the Debug
+/// output, in [`Config::redacted`] and in error messages. Use [`Secret`](crate::Secret) for the fields of your settings.
 ///
-#[ derive( Debug, Clone, PartialEq, Eq, Default, Deserialize ) ]
+/// A layer can lock values with the `!locked` tag, so the layers above it can not change them, eg. an administrator can pin
+/// `db_path: !locked /var/lib/my_app/db.sqlite` in a system wide file. [`Config::merge_layer`] refuses input that overrides
+/// a locked value with [`EkkeCfgError::Locked`](crate::EkkeCfgError::Locked). When a loaded layer, like a userset file, overrides
+/// a locked value, the override is ignored and reported in [`Config::warnings`]. See [`Config::locks`] for which layer locks what.
+///
+/// A layer can remove a value set by the layers below with `!unset`, eg. `log_file: !unset`, or remove an entry from a
+/// mapping. Setting a value to null works as well. Removing a value the settings can not do without, like a field
+/// that is not an `Option`, fails with [`EkkeCfgError::UnsetConfig`](crate::EkkeCfgError::UnsetConfig).
+///
+/// Rules for the values of the settings, like ranges or allowed strings, can be checked with [`Validate`](crate::Validate).
+/// Once enabled with [`Config::validated`], a change that breaks them fails with [`EkkeCfgError::Invalid`](crate::EkkeCfgError::Invalid)
+/// and the config is left unchanged.
+///
+#[ derive( Clone, PartialEq, Eq, Default, Deserialize ) ]
 //
 pub struct Config<T> where T: Clone + Serialize + Debug
 {
@@ -16,100 +75,1075 @@ pub struct Config<T> where T: Clone + Serialize + Debug
 	usr_path : Option< PathBuf > ,
 	def_path : Option< PathBuf > ,
 
-	default  :         Mapping   ,
-	userset  : Option< Mapping > ,
-	runtime  : Option< Mapping > ,
+	layers    : Vec< Layer >      ,
+	sensitive : Vec< String >     ,
+	strategies: Strategies        ,
+
+	#[ serde( skip ) ]
+	//
+	warnings : Vec< String >     ,
+
+	#[ serde( skip ) ]
+	//
+	sources  : Sources           ,
+
+	#[ serde( skip ) ]
+	//
+	resolvers: Resolvers         ,
+
+	#[ serde( skip ) ]
+	//
+	policy   : Policy            ,
+
+	#[ serde( skip ) ]
+	//
+	history  : History           ,
+
+	#[ serde( skip ) ]
+	//
+	check    : Check<T>          ,
+
+	#[ serde( skip ) ]
+	//
+	schema   : LayerSchema       ,
 }
 
 
 
 impl<T> Config<T> where T: Clone + DeserializeOwned + Serialize + Debug
 {
+	/// Create a config from layers, merged in the given order.
+	///
+	pub fn from_layers( layers: Vec< Layer > ) -> EkkeResult< Self >
+	{
+		Self::assemble( layers, Vec::new(), Strategies::new(), LayerSchema::default() )
+	}
+
+
+	// Create a config from layers, with the given sensitive pointers and merge strategies. The layers are checked
+	// against the schema before merging.
+	//
+	fn assemble( layers: Vec< Layer >, sensitive: Vec< String >, strategies: Strategies, schema: LayerSchema ) -> EkkeResult< Self >
+	{
+		schema.check( &layers, &sensitive )?;
+
+		let ( settings, warnings ) = generate( &layers, &sensitive, &strategies )?;
+
+		let mut cfg = Config
+		{
+			settings        ,
+			layers          ,
+			sensitive       ,
+			strategies      ,
+			warnings        ,
+			schema          ,
+
+			usr_path : None                 ,
+			def_path : None                 ,
+			sources  : Sources::default()   ,
+			resolvers: Resolvers::default() ,
+			policy   : Policy::default()    ,
+			history  : History::default()   ,
+			check    : Check::default()     ,
+		};
+
+		cfg.hide();
+
+		Ok( cfg )
+	}
+
+
+	/// Create a config by loading sources, merged in the given order. Optional sources that are
+	/// not present are skipped. The sources are kept, so [`Config::reload`] can load them again.
+	///
+	pub fn from_sources<I>( sources: I ) -> EkkeResult< Self >
+
+		where I: IntoIterator< Item = Arc< dyn Source > >
+	{
+		Self::load( sources.into_iter().collect(), Resolvers::default(), Vec::new(), Strategies::new(), LayerSchema::default() )
+	}
+
+
+	// Load sources, resolving tags with the given resolvers.
+	//
+	pub( crate ) fn load( sources: Vec< Arc< dyn Source > >, resolvers: Resolvers, sensitive: Vec< String >, strategies: Strategies, schema: LayerSchema ) -> EkkeResult< Self >
+	{
+		let mut layers = Vec::with_capacity( sources.len() );
+
+		for source in &sources
+		{
+			if let Some( layer ) = load_layer( source.as_ref(), &resolvers, &strategies )?
+			{
+				layers.push( layer );
+			}
+		}
+
+		let mut cfg = Self::assemble( layers, sensitive, strategies, schema )?;
+
+		cfg.history.set_saved( cfg.layer( Layer::USERSET ).cloned() );
+		cfg.sources   = Sources( sources );
+		cfg.resolvers = resolvers;
+
+		Ok( cfg )
+	}
+
+
+	/// Create a config from a defaults configuration, the format accepted by `Config::try_from`. It
+	/// has the settings under a `default` key in the root and optionally meta keys like `userset`.
+	///
+	/// Relative paths in meta keys are resolved against `base_dir`. When there is no base directory,
+	/// they are relative to the current working directory. When loading the defaults from a file with
+	/// `Config::try_from( &Path )`, the directory of the defaults file is used.
+	///
+	/// Paths in meta keys can contain placeholders, written as `$VAR` or `${VAR}`, and `~` for the home directory:
+	///
+	/// - `${APP_DIR}`: the directory of the running executable
+	/// - `${DEFAULTS_DIR}`: the base directory
+	/// - `${XDG_CONFIG_HOME}`, `${XDG_DATA_HOME}` and `${XDG_CACHE_HOME}`, which fall back to the defaults from
+	///   the XDG base directory specification when the environment variable is not set
+	/// - any environment variable
+	///
+	/// Referring to a variable that is not set is an error: [`EkkeCfgError::UnsetVariable`].
+	///
+	/// The optional `key_file` meta key is the path of the key to decrypt `!encrypted` values with, see
+	/// [`EncryptionKey`](crate::EncryptionKey). It's resolved like `userset`.
+	///
+	/// The optional `schema` meta key is the path of a JSON Schema file to check the layers against, see `Config::set_schema`.
+	/// It's resolved like `userset` and requires the `schema` feature.
+	///
+	/// The optional `sensitive` meta key takes a list of json pointers whose values should never be shown, eg. `[ /db/password ]`.
+	///
+	/// The optional `merge` meta key maps json pointers to the [`MergeStrategy`](crate::MergeStrategy) for the sequence there,
+	/// eg. `{ /other_comp/primes: append }`.
+	///
+	/// Relative `!include` paths in the defaults are resolved against `base_dir` as well. Tagged values are resolved
+	/// with the default [`TagResolver`](crate::TagResolver)s. Use a [`ConfigBuilder`](crate::ConfigBuilder) to add others.
+	///
+	pub fn from_defaults( input: &str, base_dir: Option< &Path > ) -> EkkeResult< Self >
+	{
+		let ( meta, tags ) = Document::parse( input )?.into_parts();
+
+		let mut meta      = match meta
+		{
+			Value::Mapping( meta ) => meta,
+			_                      => return Err( EkkeCfgError::ConfigParse.context( "The root of the default configuration must be a mapping." ).into() ),
+		};
+
+		let mut usr_path  = None;
+		let mut resolvers = Resolvers::default();
+
+		// Get the userset config file
+		// If it's present...
+		//
+		if let Some( path ) = meta.get( &"userset".into() )
+		{
+			// ...it has to be a string
+			//
+			match path
+			{
+				Value::String( path ) => usr_path = Some( resolve_meta_path( path, base_dir )? ),
+				_                     => return Err( EkkeCfgError::ConfigParse.context( "usr_path must be a string" ).into() )
+			}
+		}
+
+		// Get the key to decrypt values with
+		//
+		if let Some( path ) = meta.get( &"key_file".into() )
+		{
+			match path
+			{
+				Value::String( path ) => resolvers.add( Arc::new( EncryptedTag::from_file( resolve_meta_path( path, base_dir )? ) ) ),
+				_                     => return Err( EkkeCfgError::ConfigParse.context( "key_file must be a string" ).into() )
+			}
+		}
+
+		// Get the JSON Schema to check the layers against
+		//
+		let schema = match meta.get( &"schema".into() )
+		{
+			Some( Value::String( path ) ) => LayerSchema::from_file( &resolve_meta_path( path, base_dir )? )?,
+			Some( _ )                     => return Err( EkkeCfgError::ConfigParse.context( "schema must be a string" ).into() ),
+			None                          => LayerSchema::default(),
+		};
+
+		// Get the pointers to sensitive values
+		//
+		let sensitive = match meta.get( &"sensitive".into() )
+		{
+			None => Vec::new(),
+
+			Some( Value::Sequence( ptrs ) ) => ptrs.iter().map( |ptr| match ptr
+			{
+				Value::String( ptr ) => Ok( ptr.clone() ),
+				_                    => Err( EkkeCfgError::ConfigParse.context( "sensitive must be a list of json pointers" ) ),
+
+			}).collect::< Result< Vec<_>, _ > >()?,
+
+			_ => return Err( EkkeCfgError::ConfigParse.context( "sensitive must be a list of json pointers" ).into() )
+		};
+
+		// Get the merge strategies for sequences
+		//
+		let strategies: Strategies = match meta.get( &"merge".into() )
+		{
+			Some( merge ) => serde_yaml::from_value( merge.clone() ).context( "merge must map json pointers to merge strategies" )?,
+			None          => Strategies::new(),
+		};
+
+		// Get client settings as &mut Mapping without the metas
+		//
+		let data =
+
+			val2map_mut
+			(
+				meta.get_mut( &"default".into() )
+
+				.ok_or( EkkeCfgError::ConfigParse.context( "Default configuration must have a 'default' key in the root." ) )?
+
+			).context( "The 'default' entry in the configuration root must be an object." )?;
+
+
+		// Store the actual settings as defaults, with their tags
+		//
+		let mut default = Mapping::new();
+		std::mem::swap( &mut default, data );
+
+		let default = Document::from_parts( default.into(), tags.below( "/default" ) );
+		let default = make_layer( Layer::DEFAULT, default, base_dir, &resolvers )?;
+
+
+		// Read the userset config file
+		//
+		let mut layers = vec![ default ];
+
+		if let Some( path ) = &usr_path
+		{
+			layers.push( userset_layer( path, &resolvers )? );
+		}
+
+
+		// Generate the final settings
+		//
+		let mut cfg = Config::assemble( layers, sensitive, strategies, schema )?;
+
+		cfg.history.set_saved( cfg.layer( Layer::USERSET ).cloned() );
+		cfg.usr_path  = usr_path;
+		cfg.resolvers = resolvers;
+
+		Ok( cfg )
+	}
+
+
 	/// Merge userset settings into this config. Usually userset configuration comes
 	/// from a file in the users home directory, but in case the program allows modifying
 	/// configuration from a dialog, user configuration might change on runtime.
 	///
-	pub fn merge_userset( &mut self, input: &str ) -> MergeResult<()>
+	pub fn merge_userset( &mut self, input: &str ) -> MergeResult<()>
+	{
+		self.merge_layer( Layer::USERSET, input )
+	}
+
+
+	/// Add runtime configuration to the Config object. This will automatically be reflected
+	/// in the output of .get()
+	///
+	pub fn merge_runtime( &mut self, input: &str ) -> MergeResult<()>
+	{
+		self.merge_layer( Layer::RUNTIME, input )
+	}
+
+
+	/// Merge settings into the layer with the given name. If there is no such layer yet, it is
+	/// created. New layers go on top, except for the default layer, which goes at the bottom and
+	/// the userset layer, which is put below the runtime layer.
+	///
+	/// Relative `!include` paths in the input are resolved against the current working directory.
+	///
+	/// Fails with [`EkkeCfgError::Locked`](crate::EkkeCfgError::Locked) if the input overrides a value locked by a layer below.
+	/// When merging fails, the config is left unchanged. To make several changes at once, use a [`Config::transaction`].
+	///
+	pub fn merge_layer( &mut self, name: &str, input: &str ) -> MergeResult<()>
+	{
+		self.atomic( |cfg| cfg.stage_merge( name, input ) )
+	}
+
+
+	/// Merge a value into the userset layer. See [`Config::merge_layer_value`].
+	///
+	pub fn merge_userset_value( &mut self, value: impl Serialize ) -> EkkeResult<()>
+	{
+		self.merge_layer_value( Layer::USERSET, value )
+	}
+
+
+	/// Merge a value into the runtime layer. See [`Config::merge_layer_value`].
+	///
+	pub fn merge_runtime_value( &mut self, value: impl Serialize ) -> EkkeResult<()>
+	{
+		self.merge_layer_value( Layer::RUNTIME, value )
+	}
+
+
+	/// Merge a value into the layer with the given name, like [`Config::merge_layer`] does with yaml text. The value
+	/// can be anything that serializes to a mapping: a struct, a map, or a `serde_yaml` [`Value`] or [`Mapping`].
+	/// Strings in the value are taken as they are, so there is no need to escape them. They can't refer to other
+	/// values with `${...}`: the layer stores every `${` as `$${`. Neither do tags apply, a key like `!file` is just a key:
+	///
+	/// ```
+	/// # use ekke_config::Config;
+	/// # use std::convert::TryFrom;
+	/// # #[ derive( serde::Serialize, serde::Deserialize, Debug, Clone ) ] struct Settings { my_app: MyApp }
+	/// # #[ derive( serde::Serialize, serde::Deserialize, Debug, Clone ) ] struct MyApp { db_path: String }
+	/// #
+	/// let mut config: Config<Settings> = Config::try_from( "default: { my_app: { db_path: db.sqlite } }" )?;
+	///
+	/// let path = "my files/db: main.sqlite";
+	///
+	/// config.merge_runtime_value( Settings { my_app: MyApp { db_path: path.to_string() } } )?;
+	///
+	/// assert_eq!( config.get().my_app.db_path, path );
+	/// # Ok::<(), failure::Error>(())
+	/// ```
+	///
+	/// Note that [`Secret`](crate::Secret) values serialize as `***`, so they can not be merged this way.
+	///
+	pub fn merge_layer_value( &mut self, name: &str, value: impl Serialize ) -> EkkeResult<()>
+	{
+		let data = to_mapping( value )?;
+
+		self.atomic( |cfg| cfg.stage_merge_data( name, data.into() ) )
+	}
+
+
+	/// Set the value at a json pointer in the userset layer. See [`Config::merge_layer_at`].
+	///
+	pub fn merge_userset_at( &mut self, ptr: &str, value: impl Serialize ) -> EkkeResult<()>
+	{
+		self.merge_layer_at( Layer::USERSET, ptr, value )
+	}
+
+
+	/// Set the value at a json pointer in the runtime layer. See [`Config::merge_layer_at`].
+	///
+	pub fn merge_runtime_at( &mut self, ptr: &str, value: impl Serialize ) -> EkkeResult<()>
+	{
+		self.merge_layer_at( Layer::RUNTIME, ptr, value )
+	}
+
+
+	/// Merge a value at a json pointer into the layer with the given name, eg. `merge_layer_at( "runtime", "/my_app/log_lvl", "info" )`
+	/// does the same as `merge_layer( "runtime", "my_app: { log_lvl: info }" )`. The tokens of the pointer are keys of mappings,
+	/// which are created as needed. The empty pointer merges the value itself, like [`Config::merge_layer_value`]. Strings
+	/// in the value are taken literally, as with [`Config::merge_layer_value`].
+	///
+	pub fn merge_layer_at( &mut self, name: &str, ptr: &str, value: impl Serialize ) -> EkkeResult<()>
+	{
+		let data = nest_at( ptr, value )?;
+
+		self.atomic( |cfg| cfg.stage_merge_data( name, data.into() ) )
+	}
+
+
+	// Merge input into a layer without regenerating the settings.
+	//
+	pub( crate ) fn stage_merge( &mut self, name: &str, input: &str ) -> EkkeResult<()>
+	{
+		self.stage_merge_data( name, Document::parse( input )? )
+	}
+
+
+	// Merge a document into a layer. Documents made from values have no tags, so tags are only resolved
+	// for yaml text.
+	//
+	pub( crate ) fn stage_merge_data( &mut self, name: &str, doc: Document ) -> EkkeResult<()>
+	{
+		let policy = self.policy.0.clone();
+
+		with_policy( &policy, || self.merge_layer_checked( name, doc ) )
+	}
+
+
+	fn merge_layer_checked( &mut self, name: &str, doc: Document ) -> MergeResult<()>
+	{
+		let layer = make_layer( name, doc, None, &self.resolvers )?;
+
+		// Only the layers below may lock values for this one.
+		//
+		let index = self.position( name ).unwrap_or_else( || self.insert_position( name ) );
+
+		check_locks( &self.layers[ ..index ], layer.data(), layer.unset(), layer.directives() )?;
+
+		// Store the data for later reference
+		//
+		match self.position( name )
+		{
+			Some( index ) => self.layers[ index ].merge( layer, &self.strategies )?,
+			None          => self.layers.insert( index, layer ) ,
+		}
+
+		Ok(())
+	}
+
+
+	/// Apply a json patch ([RFC 6902](https://tools.ietf.org/html/rfc6902)) to the userset layer. See [`Config::patch_layer`].
+	///
+	pub fn patch_userset( &mut self, patch: &str ) -> EkkeResult<()>
+	{
+		self.patch_layer( Layer::USERSET, patch )
+	}
+
+
+	/// Apply a json patch ([RFC 6902](https://tools.ietf.org/html/rfc6902)) to the runtime layer. See [`Config::patch_layer`].
+	///
+	pub fn patch_runtime( &mut self, patch: &str ) -> EkkeResult<()>
+	{
+		self.patch_layer( Layer::RUNTIME, patch )
+	}
+
+
+	/// Apply a json patch ([RFC 6902](https://tools.ietf.org/html/rfc6902)) to the layer with the given name, which
+	/// is created if it doesn't exist yet. The patch is json or yaml text, eg:
+	///
+	/// ```
+	/// # use ekke_config::Config;
+	/// # use std::convert::TryFrom;
+	/// # #[ derive( serde::Serialize, serde::Deserialize, Debug, Clone ) ] struct Settings { log_lvl: String }
+	/// #
+	/// let mut config: Config<Settings> = Config::try_from( "default: { log_lvl: debug }" )?;
+	///
+	/// config.patch_userset( r#"[ { "op": "add", "path": "/log_lvl", "value": "warn" } ]"# )?;
+	///
+	/// assert_eq!( config.get().log_lvl, "warn" );
+	/// # Ok::<(), failure::Error>(())
+	/// ```
+	///
+	/// The pointers in the patch refer to the data of the layer, not to the merged settings, so `test` operations
+	/// only see the values in this layer. All operations must succeed, the result must not override locked values
+	/// and the merged settings must still deserialize into `T`. Otherwise the config is left unchanged and the error
+	/// is returned. A failing operation is reported as [`EkkeCfgError::Patch`](crate::EkkeCfgError::Patch). Patches can't
+	/// contain tags like `!locked`.
+	///
+	pub fn patch_layer( &mut self, name: &str, patch: &str ) -> EkkeResult<()>
+	{
+		self.atomic( |cfg| cfg.stage_patch( name, patch ) )
+	}
+
+
+	pub( crate ) fn stage_patch( &mut self, name: &str, patch: &str ) -> EkkeResult<()>
+	{
+		let patch = JsonPatch::parse( parse_patch( patch )? )?;
+
+		self.change_layer( name, &Mapping::new(), &patch.touched(), |data| patch.apply( data ) )
+	}
+
+
+	/// Apply a json merge patch ([RFC 7396](https://tools.ietf.org/html/rfc7396)) to the userset layer. See [`Config::merge_patch_layer`].
+	///
+	pub fn merge_patch_userset( &mut self, patch: &str ) -> EkkeResult<()>
+	{
+		self.merge_patch_layer( Layer::USERSET, patch )
+	}
+
+
+	/// Apply a json merge patch ([RFC 7396](https://tools.ietf.org/html/rfc7396)) to the runtime layer. See [`Config::merge_patch_layer`].
+	///
+	pub fn merge_patch_runtime( &mut self, patch: &str ) -> EkkeResult<()>
+	{
+		self.merge_patch_layer( Layer::RUNTIME, patch )
+	}
+
+
+	/// Apply a json merge patch ([RFC 7396](https://tools.ietf.org/html/rfc7396)) to the layer with the given name, which
+	/// is created if it doesn't exist yet. The patch is json or yaml text. Like [`Config::merge_layer`], except that null
+	/// removes a key from the layer, so the value of the layers below shows again, and that it can't contain tags.
+	///
+	/// The patch must not override locked values and the merged settings must still deserialize into `T`. Otherwise
+	/// the config is left unchanged and the error is returned.
+	///
+	pub fn merge_patch_layer( &mut self, name: &str, patch: &str ) -> EkkeResult<()>
+	{
+		self.atomic( |cfg| cfg.stage_merge_patch( name, patch ) )
+	}
+
+
+	pub( crate ) fn stage_merge_patch( &mut self, name: &str, patch: &str ) -> EkkeResult<()>
+	{
+		let patch = parse_patch( patch )?;
+
+		let check = match &patch
+		{
+			Value::Mapping( map ) => map.clone(),
+			_                     => return Err( EkkeCfgError::ConfigParse.context( "A merge patch for a layer must be a mapping" ).into() ),
+		};
+
+		self.change_layer( name, &check, &[], |data| { merge_patch( data, &patch ); Ok(()) } )
+	}
+
+
+	// Change the data of a layer, or of a new one, with f. Fails if the change overrides a value locked by the layers below,
+	// according to the changed data and the removed pointers.
+	//
+	fn change_layer( &mut self, name: &str, changed: &Mapping, removed: &[ String ], f: impl FnOnce( &mut Value ) -> EkkeResult<()> ) -> EkkeResult<()>
+	{
+		let index = self.position( name ).unwrap_or_else( || self.insert_position( name ) );
+
+		check_locks( &self.layers[ ..index ], changed, removed, &Strategies::new() )?;
+
+		let mut data = Value::Mapping( self.layer( name ).map( |l| l.data().clone() ).unwrap_or_default() );
+
+		f( &mut data )?;
+
+		let data = match data
+		{
+			Value::Mapping( data ) => data,
+			_                      => return Err( EkkeCfgError::ConfigParse.context( "The root of a layer must be a mapping" ).into() ),
+		};
+
+		match self.position( name )
+		{
+			Some( index ) => self.layers[ index ].set_data( data ),
+			None          => self.layers.insert( index, Layer::new( name, data ) ),
+		}
+
+		Ok(())
+	}
+
+
+	// Change the layers with f and regenerate the settings. If either fails, the layers are restored, so the
+	// config is left unchanged.
+	//
+	fn atomic( &mut self, f: impl FnOnce( &mut Self ) -> EkkeResult<()> ) -> EkkeResult<()>
+	{
+		let backup = self.layers.clone();
+
+		let result = match f( self )
+		{
+			Ok(()) => self.regen(),
+			Err(e) => Err(e)      ,
+		};
+
+		match result
+		{
+			Ok (()) => self.record( &backup ),
+			Err(_ ) => self.layers = backup  ,
+		}
+
+		result
+	}
+
+
+	// Add a change from the layers before to the current ones to the history.
+	//
+	pub( crate ) fn record( &mut self, before: &[ Layer ] )
+	{
+		self.history.record( before, &self.layers );
+	}
+
+
+	/// Undo the last change to the userset or runtime layer. Returns false if there is nothing to undo.
+	///
+	/// Changes made with [`Config::merge_layer`], patches, transactions and [`Config::revert_userset`] are recorded,
+	/// as long as they affect the userset or runtime layer. A transaction is undone as a whole. The history keeps
+	/// the last 100 changes by default, see [`Config::set_history_limit`].
+	///
+	pub fn undo( &mut self ) -> EkkeResult< bool >
+	{
+		if !self.history.can_undo() { return Ok( false ) }
+
+		self.restore( self.history.position() - 1 )?;
+
+		Ok( true )
+	}
+
+
+	/// Redo the last change that was undone. Returns false if there is nothing to redo. Making a new change
+	/// forgets about the undone changes.
+	///
+	pub fn redo( &mut self ) -> EkkeResult< bool >
+	{
+		if !self.history.can_redo() { return Ok( false ) }
+
+		self.restore( self.history.position() + 1 )?;
+
+		Ok( true )
+	}
+
+
+	/// Whether there is a change to undo.
+	///
+	pub fn can_undo( &self ) -> bool
+	{
+		self.history.can_undo()
+	}
+
+
+	/// Whether there is a change to redo.
+	///
+	pub fn can_redo( &self ) -> bool
+	{
+		self.history.can_redo()
+	}
+
+
+	/// The number of states in the history. State 0 is the oldest one. It is 0 until a change is made.
+	///
+	pub fn history_len( &self ) -> usize
+	{
+		self.history.len()
+	}
+
+
+	/// The position of the current state in the history.
+	///
+	pub fn history_position( &self ) -> usize
+	{
+		self.history.position()
+	}
+
+
+	/// What changed in the userset and runtime layers between two states in the history. Returns None when
+	/// one of them does not exist. Sensitive values are redacted.
+	///
+	pub fn changes( &self, from: usize, to: usize ) -> Option< Vec< Change > >
+	{
+		let changes = self.history.changes( from, to )?;
+
+		Some( changes.into_iter().map( |change|
+		{
+			let Change{ layer, pointer, old, new } = change;
+
+			let old = old.map( |v| self.redact_at( &pointer, v ) );
+			let new = new.map( |v| self.redact_at( &pointer, v ) );
+
+			Change{ layer, pointer, old, new }
+
+		}).collect() )
+	}
+
+
+	// Redact a value that lives at ptr.
+	//
+	fn redact_at( &self, ptr: &str, value: Value ) -> Value
+	{
+		if is_sensitive( ptr, &self.sensitive ) { return Value::from( REDACTED ) }
+
+		let below: Vec< String > = self.sensitive.iter()
+
+			.filter( |s| s.starts_with( ptr ) && s[ ptr.len().. ].starts_with( '/' ) )
+			.map   ( |s| s[ ptr.len().. ].to_string() )
+			.collect()
+		;
+
+		redact( value, &below )
+	}
+
+
+	/// Set how many changes the history keeps. 0 turns the history off. When there are too many, undone changes
+	/// are forgotten before the oldest ones, so the current state is kept.
+	///
+	pub fn set_history_limit( &mut self, limit: usize )
+	{
+		self.history.set_limit( limit );
+	}
+
+
+	/// Put back the userset layer as it was last loaded from disk, throwing away the changes made since. If there
+	/// was no userset file, the userset layer is removed. This can be undone.
+	///
+	pub fn revert_userset( &mut self ) -> EkkeResult<()>
+	{
+		let saved = self.history.saved().cloned();
+
+		self.atomic( |cfg|
+		{
+			match saved
+			{
+				Some( layer ) => cfg.replace_layer( layer ),
+				None          => cfg.layers.retain( |l| l.name() != Layer::USERSET ),
+			}
+
+			Ok(())
+		})
+	}
+
+
+	/// Remove the runtime layer, so only the configuration loaded from disk or other sources applies.
+	///
+	pub fn clear_runtime( &mut self ) -> EkkeResult<()>
+	{
+		self.atomic( |cfg| { cfg.layers.retain( |l| l.name() != Layer::RUNTIME ); Ok(()) } )
+	}
+
+
+	/// Read the userset layer from disk again, throwing away the changes made since. It's loaded from the
+	/// source named [`Layer::USERSET`] if there is one, otherwise from [`Config::usr_path`]. Without either, the
+	/// userset layer is removed. Unlike [`Config::revert_userset`], this sees changes made to the file.
+	///
+	/// When reading the file or regenerating the settings fails, the config is left unchanged.
+	///
+	pub fn reset_userset( &mut self ) -> EkkeResult<()>
+	{
+		let policy = self.policy.0.clone();
+		let fresh  = with_policy( &policy, || self.read_userset() )?;
+
+		self.atomic( |cfg|
+		{
+			match fresh.clone()
+			{
+				Some( layer ) => cfg.replace_layer( layer ),
+				None          => cfg.layers.retain( |l| l.name() != Layer::USERSET ),
+			}
+
+			Ok(())
+		})?;
+
+		self.history.set_saved( fresh );
+
+		Ok(())
+	}
+
+
+	// Load the userset layer from its source or from usr_path.
+	//
+	fn read_userset( &self ) -> EkkeResult< Option< Layer > >
+	{
+		if let Some( source ) = self.sources.0.iter().find( |s| s.name() == Layer::USERSET )
+		{
+			return load_layer( source.as_ref(), &self.resolvers, &self.strategies );
+		}
+
+		match &self.usr_path
+		{
+			Some( path ) => Ok( Some( userset_layer( path, &self.resolvers )? ) ),
+			None         => Ok( None ),
+		}
+	}
+
+
+	/// Remove the value at a json pointer from the layer with the given name, so the value of the layers below
+	/// shows again. The values the layer unsets and its merge directives at or below the pointer are dropped as
+	/// well, but locks are kept. Returns whether anything was removed.
+	///
+	/// When the settings don't deserialize into `T` anymore, the config is left unchanged and the error is returned.
+	///
+	pub fn remove_key( &mut self, name: &str, ptr: &str ) -> EkkeResult< bool >
+	{
+		let mut removed = false;
+
+		self.atomic( |cfg|
+		{
+			if let Some( index ) = cfg.position( name )
+			{
+				removed = cfg.layers[ index ].remove( ptr );
+			}
+
+			Ok(())
+		})?;
+
+		Ok( removed )
+	}
+
+
+	/// Remove the layer with the given name and the source it was loaded from, so it doesn't come back on
+	/// [`Config::reload`]. Removing the userset layer also forgets about [`Config::usr_path`]. The default and
+	/// userset layers are still read again on reload when the defaults came from a file, see [`Config::def_path`].
+	///
+	/// Returns whether there was such a layer or source. When the settings don't deserialize into `T` without
+	/// the layer, the config is left unchanged and the error is returned.
+	///
+	pub fn remove_source( &mut self, name: &str ) -> EkkeResult< bool >
+	{
+		let found = self.position( name ).is_some() || self.sources.0.iter().any( |s| s.name() == name );
+
+		self.atomic( |cfg| { cfg.layers.retain( |l| l.name() != name ); Ok(()) } )?;
+
+		self.sources.0.retain( |s| s.name() != name );
+
+		if name == Layer::USERSET
+		{
+			self.usr_path = None;
+		}
+
+		Ok( found )
+	}
+
+
+	// Go to a state in the history.
+	//
+	fn restore( &mut self, position: usize ) -> EkkeResult<()>
+	{
+		let state  = self.history.state( position ).cloned().unwrap_or_else( || Snapshot::of( &self.layers ) );
+		let backup = self.layers.clone();
+
+		for ( name, layer ) in state.layers()
+		{
+			match layer
+			{
+				Some( layer ) => self.replace_layer( layer.clone() ),
+				None          => self.layers.retain( |l| l.name() != name ),
+			}
+		}
+
+		if let Err( e ) = self.regen()
+		{
+			self.layers = backup;
+			return Err( e );
+		}
+
+		self.history.set_position( position );
+
+		Ok(())
+	}
+
+
+	/// Validate the settings with [`Validate`] from now on. Every change that makes them invalid is rejected with
+	/// [`EkkeCfgError::Invalid`](crate::EkkeCfgError::Invalid), listing all violations, and the config is left unchanged.
+	/// Fails the same way when the current settings are invalid.
+	///
+	pub fn validated( mut self ) -> EkkeResult< Self > where T: Validate
+	{
+		self.set_validator( Some( T::validate ) )?;
+
+		Ok( self )
+	}
+
+
+	/// Set the function that validates the settings, like [`Config::validated`] does with [`Validate::validate`]. None
+	/// turns validation off. When the current settings are invalid, the error is returned and the validator is not set.
+	///
+	pub fn set_validator( &mut self, validator: Option< fn( &T, &mut Validator ) > ) -> EkkeResult<()>
+	{
+		let check = Check( validator );
+
+		check.run( &self.settings, &self.sensitive )?;
+
+		self.check = check;
+
+		Ok(())
+	}
+
+
+	/// Check every layer against a [JSON Schema](https://json-schema.org) before merging, from now on. Requires the `schema`
+	/// feature. The schema describes the settings, like the `default` key of a defaults file. Since the layers above the
+	/// defaults only override some values, missing required properties and null values are only reported for the default layer.
+	///
+	/// All violations of all layers are reported at once, with the pointer of the value, the layer and where the layer came from,
+	/// as [`EkkeCfgError::Invalid`](crate::EkkeCfgError::Invalid). A change that breaks the schema is rejected and the config is
+	/// left unchanged. None stops checking. When the current layers break the schema, the error is returned and the schema is not set.
+	///
+	#[ cfg( feature = "schema" ) ]
+	//
+	pub fn set_schema( &mut self, schema: Option< &JsonValue > ) -> EkkeResult<()>
+	{
+		self.use_schema( LayerSchema::compile( schema )? )
+	}
+
+
+	/// Read a JSON Schema from a json or yaml file and check the layers against it, see [`Config::set_schema`].
+	///
+	#[ cfg( feature = "schema" ) ]
+	//
+	pub fn set_schema_file( &mut self, path: impl AsRef< Path > ) -> EkkeResult<()>
+	{
+		let policy = self.policy.0.clone();
+		let schema = with_policy( &policy, || LayerSchema::from_file( path.as_ref() ) )?;
+
+		self.use_schema( schema )
+	}
+
+
+	#[ cfg( feature = "schema" ) ]
+	//
+	fn use_schema( &mut self, schema: LayerSchema ) -> EkkeResult<()>
+	{
+		schema.check( &self.layers, &self.sensitive )?;
+
+		self.schema = schema;
+
+		Ok(())
+	}
+
+
+	/// Start a transaction to make several changes to the layers, which are validated and applied together
+	/// when it's committed. See [`Transaction`](crate::Transaction).
+	///
+	pub fn transaction( &mut self ) -> Transaction< '_, T >
+	{
+		Transaction::new( self )
+	}
+
+
+	// Replace the layers without regenerating the settings, to roll back staged changes.
+	//
+	pub( crate ) fn restore_layers( &mut self, layers: Vec< Layer > )
+	{
+		self.layers = layers;
+	}
+
+
+	/// Get a reference to the actual settings. These are a result of merging all layers.
+	///
+	pub fn get( &self ) -> &T
+	{
+		&self.settings
+	}
+
+
+	/// Get a copy of the defaults. This is the layer named [`Layer::DEFAULT`], or an empty
+	/// mapping if there is no such layer.
+	///
+	pub fn default( &self ) -> Value
+	{
+		self.layer( Layer::DEFAULT ).map( Layer::value ).unwrap_or_else( || Mapping::new().into() )
+	}
+
+
+	/// Get a copy of the settings that where set by the user.
+	///
+	pub fn userset( &self ) -> Option< Value >
+	{
+		self.layer( Layer::USERSET ).map( Layer::value )
+	}
+
+
+	/// Get a copy of the settings that where added at runtime
+	///
+	pub fn runtime( &self ) -> Option< Value >
+	{
+		self.layer( Layer::RUNTIME ).map( Layer::value )
+	}
+
+
+	/// All layers in the order they are merged.
+	///
+	pub fn layers( &self ) -> &[ Layer ]
+	{
+		&self.layers
+	}
+
+
+	/// Get the layer with the given name.
+	///
+	pub fn layer( &self, name: &str ) -> Option< &Layer >
+	{
+		self.layers.iter().find( |l| l.name() == name )
+	}
+
+
+
+	/// All files this config was read from: the defaults file, the files and directories of layers loaded
+	/// from disk and all the files they include. Watch these for changes to know when to call [`Config::reload`].
+	///
+	pub fn files( &self ) -> Vec< PathBuf >
+	{
+		let mut files: Vec< PathBuf > = self.def_path.iter().cloned().collect();
+
+		for layer in &self.layers
+		{
+			let origin = layer.origin().map( PathBuf::from ).filter( |path| path.exists() );
+
+			for file in origin.into_iter().chain( layer.includes().iter().cloned() )
+			{
+				if !files.contains( &file ) { files.push( file ); }
+			}
+		}
+
+		files
+	}
+
+
+
+	/// The merge strategies for sequences, by json pointer.
+	///
+	pub fn merge_strategies( &self ) -> &BTreeMap< String, MergeStrategy >
+	{
+		&self.strategies
+	}
+
+
+	/// Set how the sequence at a json pointer is merged with the sequences of the layers below, and
+	/// regenerate the settings. If that fails, the previous strategy is kept.
+	///
+	pub fn set_merge_strategy( &mut self, ptr: impl Into<String>, strategy: MergeStrategy ) -> EkkeResult<()>
 	{
-		let us: Mapping = from_str( input )?;
+		let ptr      = ptr.into();
+		let previous = self.strategies.insert( ptr.clone(), strategy );
 
-		// Store runtime for later reference
-		//
-		match &mut self.userset
+		let result = self.regen();
+
+		if result.is_err()
 		{
-			None        => { self.userset = Some( us ); }
-			Some( cfg ) => { cfg.merge( us.clone() )? ; }
+			match previous
+			{
+				Some( previous ) => self.strategies.insert( ptr, previous ),
+				None             => self.strategies.remove( &ptr ),
+			};
 		}
 
-		// Regenerate self.settings.
-		//
-		self.regen()?;
+		result
+	}
 
-		Ok(())
+
+
+	/// The locked json pointers, with the layer that locks them, from the bottom layer up.
+	///
+	pub fn locks( &self ) -> Vec<( &str, &Layer )>
+	{
+		self.layers.iter().flat_map( |layer| layer.locked().iter().map( move |ptr| ( ptr.as_str(), layer ) ) ).collect()
 	}
 
 
-	/// Add runtime configuration to the Config object. This will automatically be reflected
-	/// in the output of .get()
+	/// Problems found while generating the settings that did not make it fail, like a layer overriding
+	/// a locked value. They are replaced every time the settings are generated.
 	///
-	pub fn merge_runtime( &mut self, input: &str ) -> MergeResult<()>
+	pub fn warnings( &self ) -> &[ String ]
 	{
-		let rt: Mapping = from_str( input )?;
+		&self.warnings
+	}
 
-		// Store runtime for later reference
-		//
-		match &mut self.runtime
-		{
-			None        => { self.runtime = Some( rt ); }
-			Some( cfg ) => { cfg.merge( rt.clone() )? ; }
-		}
 
-		// Regenerate the settings with runtime merged in.
-		//
-		self.regen()?;
 
-		Ok(())
+	/// The policy files are checked against when the config reads them again, see [`FilePolicy`](crate::FilePolicy).
+	///
+	pub fn file_policy( &self ) -> Option< &FilePolicy >
+	{
+		self.policy.0.as_ref()
 	}
 
 
-	/// Get a reference to the actual settings. These are a result of merging in defaults,
-	/// userset and runtime.
+	/// Set the policy to check files against on [`Config::reload`] and for includes in [`Config::merge_layer`].
+	/// To check the files read when creating the config, use [`FilePolicy::apply`](crate::FilePolicy::apply) or
+	/// [`ConfigBuilder::file_policy`](crate::ConfigBuilder::file_policy).
 	///
-	pub fn get( &self ) -> &T
+	pub fn set_file_policy( &mut self, policy: Option< FilePolicy > )
 	{
-		&self.settings
+		self.policy = Policy( policy );
 	}
 
 
-	/// Get a copy of the defaults.
+	/// The json pointers of sensitive values.
 	///
-	pub fn default( &self ) -> Value
+	pub fn sensitive( &self ) -> &[ String ]
 	{
-		self.default.clone().into()
+		&self.sensitive
 	}
 
 
-	/// Get a copy of the settings that where set by the user.
+	/// Mark the value at a json pointer, and everything below it, as sensitive.
 	///
-	pub fn userset( &self ) -> Option< Value >
+	pub fn add_sensitive( &mut self, ptr: impl Into<String> )
 	{
-		match &self.userset
-		{
-			Some( value ) => Some( value.clone().into() ),
-			None          => None                        ,
-		}
+		self.sensitive.push( ptr.into() );
+		self.hide();
 	}
 
 
-	/// Get a copy of the settings that where added at runtime
+	/// The merged settings with sensitive values replaced by `***`, for showing or exporting the configuration.
+	/// [`Secret`](crate::Secret) fields are redacted as well.
 	///
-	pub fn runtime( &self ) -> Option< Value >
+	pub fn redacted( &self ) -> EkkeResult< Value >
 	{
-		match &self.runtime
-		{
-			Some( value ) => Some( value.clone().into() ),
-			None          => None                        ,
-		}
+		Ok( redact( serde_yaml::to_value( &self.settings )?, &self.sensitive ) )
 	}
 
 
@@ -155,116 +1189,228 @@ impl<T> Config<T> where T: Clone + DeserializeOwned + Serialize + Debug
 	}
 
 
-	// Regenerate the final settings from intermediate values. For when userset or runtime
-	// have changed.
-	//
-	fn regen( &mut self ) -> MergeResult<()>
+	/// Re-read the configuration files from disk. If the defaults where loaded from a file, this
+	/// reparses it, including the userset file it points to. Otherwise only the userset file is
+	/// reread, if there is one. When the config was created from sources, all reloadable sources
+	/// are loaded again. Other layers, like runtime, are preserved and merged in again.
+	///
+	/// Note that this replaces any changes made with `merge_userset` or `merge_layer` to reloaded layers.
+	///
+	pub fn reload( &mut self ) -> EkkeResult<()>
 	{
-		let mut settings = self.default.clone();
+		let policy = self.policy.0.clone();
 
-		if let Some( us ) = &self.userset { settings.merge( us.clone() )?; }
-		if let Some( rt ) = &self.runtime { settings.merge( rt.clone() )?; }
+		with_policy( &policy, || self.reload_checked() )
+	}
 
-		self.settings = from_str( &serde_yaml::to_string( &settings )? )?;
 
-		Ok(())
-	}
-}
+	// Reload the layers and regenerate the settings. If either fails, the config is left unchanged.
+	//
+	fn reload_checked( &mut self ) -> EkkeResult<()>
+	{
+		let layers   = self.layers.clone();
+		let usr_path = self.usr_path.clone();
+		let saved    = self.history.saved().cloned();
 
+		let result = self.reload_layers().and_then( |()| self.regen() );
 
+		if result.is_err()
+		{
+			self.layers   = layers;
+			self.usr_path = usr_path;
+			self.history.set_saved( saved );
+		}
 
-/// Convert from yaml string
-///
-impl<T> TryFrom< &str > for Config<T> where T: Clone + DeserializeOwned + Serialize + Debug
-{
+		result
+	}
 
-	type Error = Error;
 
-	fn try_from( input: &str ) -> Result< Self, Self::Error >
+	fn reload_layers( &mut self ) -> EkkeResult<()>
 	{
-		let mut meta   : Mapping           = from_str( input )? ;
-		let mut userset: Option< Mapping > = None;
+		let sources = self.sources.0.clone();
 
-		let mut usr_path = None;
-
-		// Get the userset config file
-		// If it's present...
-		//
-		if let Some( path ) = meta.get( &"userset".into() )
+		for ( i, source ) in sources.iter().enumerate().filter( |(_, s)| s.reloadable() )
 		{
-			// ...it has to be a string
-			//
-			match path
+			match load_layer( source.as_ref(), &self.resolvers, &self.strategies )?
 			{
-				Value::String( path ) => usr_path = Some( PathBuf::from( path ) ),
-				_                     => return Err( EkkeCfgError::ConfigParse.context( "usr_path must be a string" ).into() )
+				None => self.layers.retain( |l| l.name() != source.name() ),
+
+				Some( layer ) if layer.name() == Layer::USERSET =>
+				{
+					self.history.set_saved( Some( layer.clone() ) );
+					self.replace_layer( layer );
+				}
+
+				Some( layer ) => match self.position( layer.name() )
+				{
+					Some( index ) => self.layers[ index ] = layer,
+
+					// An optional source that wasn't there before. Put it right above the layer
+					// of the closest source before it.
+					//
+					None =>
+					{
+						let index = sources[ ..i ].iter().rev()
+
+							.find_map( |s| self.position( s.name() ) )
+							.map( |index| index + 1 )
+							.unwrap_or( 0 )
+						;
+
+						self.layers.insert( index, layer );
+					}
+				}
 			}
 		}
 
-		// Get client settings as &mut Mapping without the metas
-		//
-		let data =
 
-			val2map_mut
-			(
-				meta.get_mut( &"default".into() )
+		if let Some( path ) = &self.def_path
+		{
+			let fresh: Config<T> = Config::try_from( path )?;
 
-				.ok_or( EkkeCfgError::ConfigParse.context( "Default configuration must have a 'default' key in the root." ) )?
+			for name in &[ Layer::DEFAULT, Layer::USERSET ]
+			{
+				match fresh.layer( name )
+				{
+					Some( layer ) => self.replace_layer( layer.clone() ),
+					None          => self.layers.retain( |l| l.name() != *name ),
+				}
+			}
 
-			).context( "The 'default' entry in the configuration root must be an object." )?;
+			self.usr_path = fresh.usr_path;
+			self.history.set_saved( fresh.history.saved().cloned() );
+		}
 
+		else if let Some( path ) = &self.usr_path
+		{
+			let layer = userset_layer( path, &self.resolvers )?;
 
-		// Store the actual settings as defaults
-		//
-		let mut default = Mapping::new();
-		std::mem::swap( &mut default, data );
+			self.history.set_saved( Some( layer.clone() ) );
+			self.replace_layer( layer );
+		}
+
+		Ok(())
+	}
 
 
-		// Read the userset config file
-		//
-		if let Some( path ) = &usr_path
+	// Replace the layer with the same name, or insert it if there is none.
+	//
+	fn replace_layer( &mut self, layer: Layer )
+	{
+		match self.position( layer.name() )
 		{
-			let users: Mapping =
+			Some( index ) => self.layers[ index ] = layer,
+			None          => self.insert_layer( layer )  ,
+		}
+	}
 
-				// This reads the file from the userset property as given in defaults.
-				// shellexpand::tilde wil expand the home directory.
-				// TODO: we should probably use path.to_str and throw an error if it's not valid unicode
-				// TODO: make cross platform
-				//
-				from_str( &read_file( &Path::new( tilde( path.to_string_lossy().as_ref() ).as_ref() ) )
 
-				.context( format!( "{:?}", path ) )? ).context( format!( "Failed to parse yaml at: {:?}", path ) )?
-			;
+	// Insert a new layer. Defaults go at the bottom, userset goes below runtime and
+	// everything else on top.
+	//
+	fn insert_layer( &mut self, layer: Layer )
+	{
+		let index = self.insert_position( layer.name() );
+
+		self.layers.insert( index, layer );
+	}
 
-			userset = Some( users.clone() );
+
+	fn insert_position( &self, name: &str ) -> usize
+	{
+		match name
+		{
+			Layer::DEFAULT => 0                                                                ,
+			Layer::USERSET => self.position( Layer::RUNTIME ).unwrap_or( self.layers.len() ),
+			_              => self.layers.len()                                              ,
 		}
+	}
 
 
-		// Generate the final settings
-		//
-		let mut def = default.clone();
+	fn position( &self, name: &str ) -> Option< usize >
+	{
+		self.layers.iter().position( |l| l.name() == name )
+	}
 
-		// Merge userset
-		//
-		if let Some( us ) = &userset { def.merge( us.clone() )?; }
 
+	// Regenerate the final settings from intermediate values. For when layers have changed.
+	//
+	pub( crate ) fn regen( &mut self ) -> MergeResult<()>
+	{
+		self.schema.check( &self.layers, &self.sensitive )?;
 
-		// use deserialize, serialize to convert Mapping to T
-		//
-		let settings: T = from_str( &serde_yaml::to_string( &def )? )?;
+		let ( settings, warnings ) = generate( &self.layers, &self.sensitive, &self.strategies )?;
+
+		self.check.run( &settings, &self.sensitive )?;
+
+		self.settings = settings;
+		self.warnings = warnings;
+
+		self.hide();
+
+		Ok(())
+	}
+
+
+	// Tell the layers which values to hide in their Debug output: the sensitive ones and the values of
+	// Secret fields, which serialize as `***`.
+	//
+	fn hide( &mut self )
+	{
+		let mut hidden = self.sensitive.clone();
 
-		dbg!( &settings );
+		if let Ok( settings ) = serde_yaml::to_value( &self.settings )
+		{
+			hidden.extend( secret_pointers( &settings ) );
+		}
 
-		Ok( Config
+		for layer in &mut self.layers
 		{
-			default         ,
-			settings        ,
-			userset         ,
+			layer.hide( hidden.clone() );
+		}
+	}
+}
 
-			usr_path        ,
-			def_path: None  ,
-			runtime : None  ,
-		})
+
+
+/// Sensitive values and the values of [`Secret`](crate::Secret) fields are redacted from the layers, which do that
+/// themselves. When there are sensitive pointers, the settings are shown as yaml values, so they can be redacted as well.
+///
+impl<T> Debug for Config<T> where T: Clone + Serialize + Debug
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		let settings = serde_yaml::to_value( &self.settings );
+		let mut out  = f.debug_struct( "Config" );
+
+		match settings
+		{
+			Ok( settings ) if !self.sensitive.is_empty() => out.field( "settings", &redact( settings, &self.sensitive ) ),
+			_                                            => out.field( "settings", &self.settings                       ),
+		};
+
+		out
+			.field( "usr_path" , &self.usr_path  )
+			.field( "def_path" , &self.def_path  )
+			.field( "layers"   , &self.layers    )
+			.field( "sensitive", &self.sensitive )
+			.field( "warnings" , &self.warnings  )
+			.finish()
+	}
+}
+
+
+
+/// Convert from yaml string
+///
+impl<T> TryFrom< &str > for Config<T> where T: Clone + DeserializeOwned + Serialize + Debug
+{
+
+	type Error = Error;
+
+	fn try_from( input: &str ) -> Result< Self, Self::Error >
+	{
+		Config::from_defaults( input, None )
 	}
 }
 
@@ -297,11 +1443,16 @@ impl<T> TryFrom< &Path > for Config<T> where T: Clone + DeserializeOwned + Seria
 
 	fn try_from( path: &Path ) -> Result< Self, Self::Error >
 	{
-		let file = File::open( path ).context( format!( "{:?}", path ) )?;
-		let mut cfg = Config::try_from( &file )?;
+		let input   = read_file( path ).context( format!( "{:?}", path ) )?;
+		let mut cfg = Config::from_defaults( &input, path.parent() )?;
 
 		cfg.def_path = Some( PathBuf::from( path ) );
 
+		if let Some( index ) = cfg.position( Layer::DEFAULT )
+		{
+			cfg.layers[ index ].set_origin( Some( path.to_string_lossy().into_owned() ) );
+		}
+
 		Ok( cfg )
 	}
 }
@@ -336,15 +1487,213 @@ fn val2map_mut( val: &mut Value ) -> EkkeResult< &mut Mapping >
 }
 
 
-fn read_file( path: &Path ) -> EkkeResult< String >
+// Resolve a path from a meta key, like userset, in the defaults configuration.
+// Placeholders are expanded and relative paths are taken relative to base_dir.
+//
+fn resolve_meta_path( path: &str, base_dir: Option< &Path > ) -> EkkeResult< PathBuf >
+{
+	let path = expand_path( path, base_dir ).context( format!( "Failed to resolve path: {}", path ) )?;
+
+	Ok( match base_dir
+	{
+		Some( dir ) if path.is_relative() => dir.join( path ),
+		_                                 => path            ,
+	})
+}
+
+
+// Run f with the file policy, if there is one.
+//
+fn with_policy<R>( policy: &Option< FilePolicy >, f: impl FnOnce() -> EkkeResult<R> ) -> EkkeResult<R>
+{
+	match policy
+	{
+		Some( policy ) => policy.apply( f ),
+		None           => f()             ,
+	}
+}
+
+
+// The file policy of a config. It's not configuration data, so it doesn't affect equality.
+//
+#[ derive( Debug, Clone, Default ) ]
+//
+struct Policy( Option< FilePolicy > );
+
+
+impl PartialEq for Policy
+{
+	fn eq( &self, _other: &Self ) -> bool { true }
+}
+
+impl Eq for Policy {}
+
+
+// The function that validates the settings. It's not configuration data, so it doesn't affect equality.
+//
+struct Check<T>( Option< fn( &T, &mut Validator ) > );
+
+
+impl<T> Check<T>
+{
+	// Fail with all violations if the settings are invalid. The messages for sensitive values are redacted,
+	// as they might show the value.
+	//
+	fn run( &self, settings: &T, sensitive: &[ String ] ) -> EkkeResult<()>
+	{
+		let check = match self.0
+		{
+			Some( check ) => check,
+			None          => return Ok(()),
+		};
+
+		let mut validator = Validator::new();
+
+		check( settings, &mut validator );
+
+		if validator.is_valid() { return Ok(()) }
+
+		let mut violations = validator.into_violations();
+
+		violations.redact( |ptr| is_sensitive( ptr, sensitive ) );
+
+		Err( EkkeCfgError::Invalid( violations ).into() )
+	}
+}
+
+
+impl<T> Clone for Check<T>
+{
+	fn clone( &self ) -> Self { Self( self.0 ) }
+}
+
+
+impl<T> Default for Check<T>
+{
+	fn default() -> Self { Self( None ) }
+}
+
+
+impl<T> Debug for Check<T>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "Check({})", if self.0.is_some() { "Some" } else { "None" } )
+	}
+}
+
+
+impl<T> PartialEq for Check<T>
+{
+	fn eq( &self, _other: &Self ) -> bool { true }
+}
+
+impl<T> Eq for Check<T> {}
+
+
+
+// Serialize a value into data for a layer. References in its strings are escaped, so they are taken literally.
+//
+pub( crate ) fn to_mapping( value: impl Serialize ) -> EkkeResult< Mapping >
+{
+	match literal( serde_yaml::to_value( value )? )
+	{
+		Value::Mapping( data ) => Ok( data ),
+		_                      => Err( EkkeCfgError::ConfigParse.context( "A value to merge into a layer must be a mapping" ).into() ),
+	}
+}
+
+
+// The data for a layer with value at ptr.
+//
+pub( crate ) fn nest_at( ptr: &str, value: impl Serialize ) -> EkkeResult< Mapping >
+{
+	if ptr.is_empty()
+	{
+		return to_mapping( value );
+	}
+
+	if !ptr.starts_with( '/' )
+	{
+		return Err( EkkeCfgError::ConfigParse.context( format!( "Not a json pointer: {}", ptr ) ).into() );
+	}
+
+	let keys: Vec< String > = ptr.split( '/' ).skip( 1 ).map( unescape ).collect();
+
+	Ok( nest( &keys, literal( serde_yaml::to_value( value )? ) ) )
+}
+
+
+// Read the userset file and resolve its includes and tags.
+//
+fn userset_layer( path: &Path, resolvers: &Resolvers ) -> EkkeResult< Layer >
+{
+	let layer = make_layer( Layer::USERSET, parse_file( path )?, Some( path ), resolvers )?;
+
+	Ok( layer.with_origin( Some( path.to_string_lossy().into_owned() ) ) )
+}
+
+
+// Parse a patch, refusing tags since they only take effect when a layer is made.
+//
+fn parse_patch( input: &str ) -> EkkeResult< Value >
+{
+	let ( patch, tags ) = parse_tagged( input )?;
+
+	if let Some(( ptr, tag )) = tags.iter().next()
+	{
+		return Err( EkkeCfgError::ConfigParse.context( format!( "Patches can not contain tags, found {} at: {}", tag, ptr ) ).into() );
+	}
+
+	Ok( patch )
+}
+
+
+// Merge layers in order and deserialize the result into T. Overrides of locked values are left out
+// and returned as warnings.
+//
+fn generate<T>( layers: &[ Layer ], sensitive: &[ String ], strategies: &Strategies ) -> EkkeResult<( T, Vec< String > )> where T: DeserializeOwned
 {
-	let     file       = File::open( path )?;
-	let mut buf_reader = BufReader::new( file );
-	let mut contents   = String::new();
+	let ( data, warnings ) = unlocked_data( layers );
+	let mut settings       = Mapping::new();
+	let mut removed        = Vec::new();
+	let mut locked         = Vec::new();
 
-	buf_reader.read_to_string( &mut contents )?;
+	for ( layer, ( mut data, unset ) ) in layers.iter().zip( data )
+	{
+		removed.extend( apply_unsets( &mut settings, &mut data, &unset ) );
+
+		merge_with( &mut settings, data, strategies, layer.directives(), &locked ).context( format!( "Failed to merge layer: {}", layer.name() ) )?;
+
+		locked.extend( layer.locked().iter().cloned() );
+	}
+
+	let convert = |settings: Mapping| -> EkkeResult<T>
+	{
+		// Resolve references after merging, so overrides of referenced values propagate.
+		//
+		let settings = interpolate( &Value::Mapping( settings ), sensitive )?;
+
+		// use deserialize, serialize to convert Mapping to T. The errors show the values that don't fit.
+		//
+		from_str( &serde_yaml::to_string( &settings )? ).map_err( |e|
+
+			EkkeCfgError::ConfigParse.context( redact_message( &e.to_string(), &settings, sensitive ) ).into()
+		)
+	};
+
+	match convert( settings.clone() )
+	{
+		Ok( settings ) => Ok(( settings, warnings )),
 
-	Ok( contents )
+		// Tell the user which value should not have been removed.
+		//
+		Err( e ) => match required( &settings, &removed, convert )
+		{
+			Some( ptr ) => Err( EkkeCfgError::UnsetConfig( ptr ).into() ),
+			None        => Err( e ),
+		}
+	}
 }
 
 
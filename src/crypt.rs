@@ -0,0 +1,222 @@
+use crate :: { import::*, EkkeResult, EkkeCfgError, TagResolver, source::read_file };
+
+use chacha20poly1305 ::
+{
+	Key, XChaCha20Poly1305, XNonce,
+	aead::{ Aead, NewAead },
+};
+
+
+const KEY_LEN  : usize = 32;
+const NONCE_LEN: usize = 24;
+
+
+/// A key to encrypt configuration values with, so files containing credentials can be committed
+/// to a repository. Encrypted values are written with the `!encrypted` tag:
+///
+/// ```yaml
+/// db:
+///   password: !encrypted 3q2+7wAAAAAAAAAAAAAAAAAAAAAAAAAAAHGpD0...
+/// ```
+///
+/// A key is stored as base64 text in a file, which should only be readable by the users of the
+/// configuration. Values are encrypted with XChaCha20-Poly1305, so decrypting fails with
+/// [`EkkeCfgError::Decryption`] when the ciphertext has been tampered with or the key is wrong.
+///
+/// ```
+/// # use ekke_config::EncryptionKey;
+/// let key        = EncryptionKey::generate()?;
+/// let ciphertext = key.encrypt( &"hunter2" )?;
+///
+/// // Write `password: !encrypted <ciphertext>` in the configuration file
+/// //
+/// assert_eq!( key.decrypt( &ciphertext )?, serde_yaml::Value::from( "hunter2" ) );
+/// # Ok::<(), failure::Error>(())
+/// ```
+///
+#[ derive( Clone, PartialEq, Eq ) ]
+//
+pub struct EncryptionKey( [ u8; KEY_LEN ] );
+
+
+impl EncryptionKey
+{
+	/// Generate a new random key.
+	///
+	pub fn generate() -> EkkeResult< Self >
+	{
+		let mut key = [ 0; KEY_LEN ];
+
+		getrandom::getrandom( &mut key ).map_err( |e| EkkeCfgError::ConfigParse.context( format!( "Failed to generate a key: {}", e ) ) )?;
+
+		Ok( Self( key ) )
+	}
+
+
+	/// Read a key from base64 text, as written by [`EncryptionKey::to_base64`].
+	///
+	pub fn from_base64( input: &str ) -> EkkeResult< Self >
+	{
+		let bytes = base64::decode( input.trim() ).context( "An encryption key must be base64" )?;
+
+		if bytes.len() != KEY_LEN
+		{
+			return Err( EkkeCfgError::ConfigParse.context( format!( "An encryption key must be {} bytes, got {}", KEY_LEN, bytes.len() ) ).into() );
+		}
+
+		let mut key = [ 0; KEY_LEN ];
+		key.copy_from_slice( &bytes );
+
+		Ok( Self( key ) )
+	}
+
+
+	/// Read a key from a file. Fails with [`EkkeCfgError::MissingKey`] if the file does not exist.
+	///
+	pub fn from_file( path: impl AsRef<Path> ) -> EkkeResult< Self >
+	{
+		let path = path.as_ref();
+
+		if !path.exists()
+		{
+			return Err( EkkeCfgError::MissingKey.context( format!( "{:?}", path ) ).into() );
+		}
+
+		Self::from_base64( &read_file( path ).context( format!( "{:?}", path ) )? )
+
+			.context( format!( "Invalid key file: {:?}", path ) ).map_err( Into::into )
+	}
+
+
+	/// The key as base64 text, for writing it to a key file.
+	///
+	pub fn to_base64( &self ) -> String
+	{
+		base64::encode( self.0 )
+	}
+
+
+	/// Encrypt a value. It is serialized as yaml, so it keeps its type when decrypted. The result is
+	/// base64 text to put after an `!encrypted` tag.
+	///
+	pub fn encrypt<V: Serialize + ?Sized>( &self, value: &V ) -> EkkeResult< String >
+	{
+		let plaintext = serde_yaml::to_string( value )?;
+
+		let mut nonce = [ 0; NONCE_LEN ];
+
+		getrandom::getrandom( &mut nonce ).map_err( |e| EkkeCfgError::ConfigParse.context( format!( "Failed to generate a nonce: {}", e ) ) )?;
+
+		let ciphertext = self.cipher()
+
+			.encrypt( &XNonce::from( nonce ), plaintext.as_bytes() )
+			.map_err( |_| EkkeCfgError::ConfigParse.context( "Failed to encrypt value" ) )?
+		;
+
+		let mut out = nonce.to_vec();
+		out.extend( ciphertext );
+
+		Ok( base64::encode( out ) )
+	}
+
+
+	/// Decrypt a value encrypted with [`EncryptionKey::encrypt`].
+	///
+	pub fn decrypt( &self, ciphertext: &str ) -> EkkeResult< Value >
+	{
+		let bytes = base64::decode( ciphertext.trim() ).map_err( |_| EkkeCfgError::Decryption )?;
+
+		if bytes.len() < NONCE_LEN
+		{
+			return Err( EkkeCfgError::Decryption.into() );
+		}
+
+		let ( nonce_bytes, ciphertext ) = bytes.split_at( NONCE_LEN );
+
+		let mut nonce = [ 0; NONCE_LEN ];
+		nonce.copy_from_slice( nonce_bytes );
+
+		let plaintext = self.cipher()
+
+			.decrypt( &XNonce::from( nonce ), ciphertext )
+			.map_err( |_| EkkeCfgError::Decryption )?
+		;
+
+		let plaintext = String::from_utf8( plaintext ).map_err( |_| EkkeCfgError::Decryption )?;
+
+		Ok( from_str( &plaintext )? )
+	}
+
+
+	fn cipher( &self ) -> XChaCha20Poly1305
+	{
+		XChaCha20Poly1305::new( &Key::from( self.0 ) )
+	}
+}
+
+
+impl Debug for EncryptionKey
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "EncryptionKey(***)" )
+	}
+}
+
+
+
+/// `!encrypted ciphertext` is replaced by the decrypted value. See [`EncryptionKey`].
+///
+/// It is enabled by default, but without a key, so encrypted values fail with [`EkkeCfgError::MissingKey`] until a
+/// key is given with [`ConfigBuilder::key_file`](crate::ConfigBuilder::key_file) or the `key_file` meta key of the
+/// defaults. Consider marking the pointers of encrypted values as sensitive.
+///
+#[ derive( Debug, Clone, Default ) ]
+//
+pub struct EncryptedTag
+{
+	key : Option< EncryptionKey >,
+	path: Option< PathBuf       >,
+}
+
+
+impl EncryptedTag
+{
+	/// Decrypt with the given key.
+	///
+	pub fn new( key: EncryptionKey ) -> Self
+	{
+		Self { key: Some( key ), path: None }
+	}
+
+
+	/// Decrypt with the key in a file. The file is read when a value needs to be decrypted.
+	///
+	pub fn from_file( path: impl Into<PathBuf> ) -> Self
+	{
+		Self { key: None, path: Some( path.into() ) }
+	}
+}
+
+
+impl TagResolver for EncryptedTag
+{
+	fn tag( &self ) -> &str { "!encrypted" }
+
+	fn resolve( &self, value: &Value ) -> EkkeResult< Value >
+	{
+		let ciphertext = match value
+		{
+			Value::String( s ) => s,
+			_                  => return Err( EkkeCfgError::ConfigParse.context( "!encrypted takes a string" ).into() ),
+		};
+
+		match ( &self.key, &self.path )
+		{
+			( Some( key ), _          ) => key.decrypt( ciphertext ),
+			( None       , Some( p )  ) => EncryptionKey::from_file( p )?.decrypt( ciphertext ),
+
+			( None, None ) => Err( EkkeCfgError::MissingKey.context( "No key file is configured for decrypting values" ).into() ),
+		}
+	}
+}
@@ -1,4 +1,4 @@
-use crate :: { import::* };
+use crate :: { import::*, Violations };
 
 
 /// Custom result type, Allows to omit error type since it's always
@@ -13,11 +13,51 @@ pub type EkkeResult<T> = Result< T, Error >;
 //
 pub enum EkkeCfgError
 {
-	#[ fail( display = "Cannot unset default configuration value by setting it to Null" ) ]
+	#[ fail( display = "Cannot unset the required configuration value at {}", _0 ) ]
 	//
-	UnsetConfig,
+	UnsetConfig( String ),
 
 	#[ fail( display = "Failed to parse Configuration" ) ]
 	//
 	ConfigParse,
+
+	#[ fail( display = "A required configuration source is not present" ) ]
+	//
+	MissingSource,
+
+	#[ fail( display = "Placeholder refers to a variable that is not set: {}", _0 ) ]
+	//
+	UnsetVariable( String ),
+
+	#[ fail( display = "Failed to interpolate the value at {}: {}", pointer, reason ) ]
+	//
+	Interpolation { pointer: String, reason: String },
+
+	#[ fail( display = "Include cycle: {}", _0 ) ]
+	//
+	IncludeCycle( String ),
+
+	#[ fail( display = "No key is available to decrypt configuration values" ) ]
+	//
+	MissingKey,
+
+	#[ fail( display = "Failed to decrypt a value, the key is wrong or the ciphertext has been tampered with" ) ]
+	//
+	Decryption,
+
+	#[ fail( display = "The file {} violates the file policy: {}", path, reason ) ]
+	//
+	FilePolicy { path: String, reason: String },
+
+	#[ fail( display = "The value at {} is locked by layer {}", pointer, layer ) ]
+	//
+	Locked { pointer: String, layer: String },
+
+	#[ fail( display = "Operation {} of the json patch failed: {}", index, reason ) ]
+	//
+	Patch { index: usize, reason: String },
+
+	#[ fail( display = "The configuration is invalid: {}", _0 ) ]
+	//
+	Invalid( Violations ),
 }
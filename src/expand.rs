@@ -0,0 +1,41 @@
+use crate :: { import::*, EkkeResult, EkkeCfgError, xdg };
+
+
+// Expand placeholders in a path from a meta key in the defaults configuration. Supports `~` for the
+// home directory and `$VAR` or `${VAR}` for:
+//
+// - `APP_DIR`: the directory of the running executable
+// - `DEFAULTS_DIR`: the directory of the defaults file, made absolute
+// - `XDG_CONFIG_HOME`, `XDG_DATA_HOME` and `XDG_CACHE_HOME`, with the defaults from the XDG spec if unset
+// - any other environment variable
+//
+// Referring to a variable that is not set is an error.
+//
+pub( crate ) fn expand_path( input: &str, defaults_dir: Option< &Path > ) -> EkkeResult< PathBuf >
+{
+	let context = |var: &str| -> Result< Option< String >, String >
+	{
+		let value = match var
+		{
+			"APP_DIR"         => env::current_exe().ok().and_then( |exe| exe.parent().map( PathBuf::from ) )         ,
+			"DEFAULTS_DIR"    => defaults_dir.and_then( |dir| env::current_dir().ok().map( |cwd| cwd.join( dir ) ) ) ,
+			"XDG_CONFIG_HOME" => xdg::base_dir( var, ".config"      )                                                ,
+			"XDG_DATA_HOME"   => xdg::base_dir( var, ".local/share" )                                                ,
+			"XDG_CACHE_HOME"  => xdg::base_dir( var, ".cache"       )                                                ,
+			_                 => env::var_os( var ).map( PathBuf::from )                                             ,
+		};
+
+		match value
+		{
+			Some( path ) => Ok( Some( path.to_string_lossy().into_owned() ) ),
+			None         => Err( format!( "Failed to expand: {}", input ) )  ,
+		}
+	};
+
+	let expanded = env_with_context( input, context )
+
+		.map_err( |e| EkkeCfgError::UnsetVariable( e.var_name ).context( e.cause ) )?
+	;
+
+	Ok( PathBuf::from( tilde( &expanded ).as_ref() ) )
+}
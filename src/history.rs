@@ -0,0 +1,252 @@
+use crate :: { import::*, Layer, pointer::escape };
+
+
+/// A difference between two states in the history of a [`Config`](crate::Config), see [`Config::changes`](crate::Config::changes).
+/// `old` is None when the value was added and `new` is None when it was removed.
+///
+#[ derive( Debug, Clone, PartialEq, Eq ) ]
+//
+pub struct Change
+{
+	/// The name of the layer that changed.
+	///
+	pub layer  : String,
+
+	/// The json pointer of the value that changed, within the layer.
+	///
+	pub pointer: String,
+
+	/// The value before the change.
+	///
+	pub old    : Option< Value >,
+
+	/// The value after the change.
+	///
+	pub new    : Option< Value >,
+}
+
+
+
+// The layers the history keeps track of.
+//
+const TRACKED: [&str; 2] = [ Layer::USERSET, Layer::RUNTIME ];
+
+
+// The tracked layers at some point in time.
+//
+#[ derive( Debug, Clone, PartialEq, Eq ) ]
+//
+pub( crate ) struct Snapshot( Vec< Option< Layer > > );
+
+
+impl Snapshot
+{
+	pub( crate ) fn of( layers: &[ Layer ] ) -> Self
+	{
+		Self( TRACKED.iter().map( |name| layers.iter().find( |l| l.name() == *name ).cloned() ).collect() )
+	}
+
+
+	// The tracked layers to put back, or the names of the ones to remove.
+	//
+	pub( crate ) fn layers( &self ) -> impl Iterator< Item = ( &'static str, Option< &Layer > ) >
+	{
+		TRACKED.iter().copied().zip( self.0.iter().map( Option::as_ref ) )
+	}
+
+
+	fn changes( &self, other: &Snapshot ) -> Vec< Change >
+	{
+		let mut out = Vec::new();
+
+		for ( ( name, old ), new ) in self.layers().zip( &other.0 )
+		{
+			let old = Value::Mapping( old.map( |l| l.data().clone() ).unwrap_or_default() );
+			let new = Value::Mapping( new.as_ref().map( |l| l.data().clone() ).unwrap_or_default() );
+
+			diff( name, String::new(), Some( &old ), Some( &new ), &mut out );
+		}
+
+		out
+	}
+}
+
+
+
+// The undo history of the userset and runtime layers: a timeline of states and the position of the
+// current one. It's not configuration data, so it doesn't affect equality. The states are shared, so
+// cloning a config does not copy them.
+//
+#[ derive( Debug, Clone ) ]
+//
+pub( crate ) struct History
+{
+	states  : Vec< Arc< Snapshot > >,
+	position: usize                 ,
+	limit   : usize                 ,
+
+	// The userset layer as it was last loaded from disk.
+	//
+	saved   : Option< Layer >       ,
+}
+
+
+impl Default for History
+{
+	fn default() -> Self
+	{
+		Self { states: Vec::new(), position: 0, limit: 100, saved: None }
+	}
+}
+
+
+impl PartialEq for History
+{
+	fn eq( &self, _other: &Self ) -> bool { true }
+}
+
+impl Eq for History {}
+
+
+impl History
+{
+	// Record a change from the layers before to the layers after. Changes that don't affect the tracked
+	// layers are ignored. Undone states are dropped.
+	//
+	pub( crate ) fn record( &mut self, before: &[ Layer ], after: &[ Layer ] )
+	{
+		let before = Snapshot::of( before );
+		let after  = Snapshot::of( after  );
+
+		if before == after || self.limit == 0 { return }
+
+		self.states.truncate( self.position + 1 );
+
+		// Something else changed the layers since the last change, like a reload.
+		//
+		if self.states.last().map( Arc::as_ref ) != Some( &before )
+		{
+			self.states.push( Arc::new( before ) );
+		}
+
+		self.states.push( Arc::new( after ) );
+
+		let excess = self.states.len().saturating_sub( self.limit + 1 );
+
+		self.states.drain( ..excess );
+		self.position = self.states.len() - 1;
+	}
+
+
+	// The state at a position in the timeline.
+	//
+	pub( crate ) fn state( &self, position: usize ) -> Option< &Snapshot >
+	{
+		self.states.get( position ).map( Arc::as_ref )
+	}
+
+
+	// Move to another state, after it has been restored.
+	//
+	pub( crate ) fn set_position( &mut self, position: usize )
+	{
+		self.position = position;
+	}
+
+
+	pub( crate ) fn can_undo( &self ) -> bool { self.position > 0                     }
+	pub( crate ) fn can_redo( &self ) -> bool { self.position + 1 < self.states.len() }
+	pub( crate ) fn len     ( &self ) -> usize { self.states.len()                    }
+	pub( crate ) fn position( &self ) -> usize { self.position                        }
+
+
+	pub( crate ) fn set_limit( &mut self, limit: usize )
+	{
+		self.limit = limit;
+
+		if limit == 0
+		{
+			self.states.clear();
+			self.position = 0;
+
+			return
+		}
+
+		// Drop the undone states first, so the current state is kept.
+		//
+		self.states.truncate( self.position + 1 + limit );
+
+		let excess = self.states.len().saturating_sub( limit + 1 ).min( self.position );
+
+		self.states.drain( ..excess );
+		self.position -= excess;
+	}
+
+
+	pub( crate ) fn changes( &self, from: usize, to: usize ) -> Option< Vec< Change > >
+	{
+		Some( self.states.get( from )?.changes( self.states.get( to )? ) )
+	}
+
+
+	pub( crate ) fn saved( &self ) -> Option< &Layer >
+	{
+		self.saved.as_ref()
+	}
+
+
+	pub( crate ) fn set_saved( &mut self, saved: Option< Layer > )
+	{
+		self.saved = saved;
+	}
+}
+
+
+
+// The differences between two values, down to the keys of mappings. A mapping that is added or
+// removed is reported key by key.
+//
+fn diff( layer: &str, ptr: String, old: Option< &Value >, new: Option< &Value >, out: &mut Vec< Change > )
+{
+	let empty = Mapping::new();
+
+	match ( as_map( old, new, &empty ), as_map( new, old, &empty ) )
+	{
+		( Some( old ), Some( new ) ) =>
+		{
+			let added = new.iter().filter( |( key, _ )| !old.contains_key( key ) );
+
+			for ( key, _ ) in old.iter().chain( added )
+			{
+				if let Value::String( k ) = key
+				{
+					diff( layer, format!( "{}/{}", ptr, escape( k ) ), old.get( key ), new.get( key ), out );
+				}
+			}
+		}
+
+		_ if old == new => {}
+
+		_ => out.push( Change
+		{
+			layer  : layer.to_string(),
+			pointer: ptr              ,
+			old    : old.cloned()     ,
+			new    : new.cloned()     ,
+		}),
+	}
+}
+
+
+// The mapping to compare with another value: the value itself, or an empty mapping when it's missing
+// and the other value is a mapping.
+//
+fn as_map<'a>( value: Option< &'a Value >, other: Option< &Value >, empty: &'a Mapping ) -> Option< &'a Mapping >
+{
+	match ( value, other )
+	{
+		( Some( Value::Mapping( map ) ), _                          ) => Some( map   ),
+		( None                          , Some( Value::Mapping(_) ) ) => Some( empty ),
+		_                                                             => None        ,
+	}
+}
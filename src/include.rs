@@ -0,0 +1,149 @@
+use crate :: { import::*, EkkeResult, EkkeCfgError, Pointer, pointer::jptr_mut, source::read_file, yaml::{ parse_tagged, Document, Tags, INCLUDE } };
+
+
+// Replace `!include path` nodes in data by the content of the file at path. Relative paths are taken
+// relative to the file containing the include. For data that does not come from a file, `origin` is
+// the directory relative paths are resolved against, or None for the current working directory.
+//
+// When the path contains glob characters (`*`, `?` or `[`), all matching files are merged in alphabetical
+// order. They must contain mappings. A pattern that matches nothing gives an empty mapping.
+//
+// Returns the data and its tags, with those of the included files, together with the paths of all included files.
+//
+pub( crate ) fn resolve_includes( data: Value, tags: Tags, origin: Option< &Path > ) -> EkkeResult<( Value, Tags, Vec< PathBuf > )>
+{
+	let mut includes = Includes { stack: Vec::new(), files: Vec::new() };
+
+	let base = match origin
+	{
+		Some( path ) if path.is_file() =>
+		{
+			includes.stack.push( path.canonicalize()? );
+			path.parent()
+		}
+
+		other => other,
+	};
+
+	let ( data, tags ) = includes.resolve( data, tags, base )?;
+
+	match data
+	{
+		Value::Mapping(_)             => Ok(( data, tags, includes.files )),
+		_ if tags.get( "" ).is_some() => Ok(( data, tags, includes.files )),
+
+		_ => Err( EkkeCfgError::ConfigParse.context( "An include in the root of a configuration must contain a mapping." ).into() ),
+	}
+}
+
+
+
+struct Includes
+{
+	// The files currently being included, for detecting cycles.
+	//
+	stack: Vec< PathBuf >,
+	files: Vec< PathBuf >,
+}
+
+
+impl Includes
+{
+	fn resolve( &mut self, mut data: Value, mut tags: Tags, base: Option< &Path > ) -> EkkeResult<( Value, Tags )>
+	{
+		for ptr in tags.take( INCLUDE )
+		{
+			let path = match data.jptr( &ptr )
+			{
+				Some( Value::String( path ) ) => path.clone(),
+				Some( _                     ) => return Err( EkkeCfgError::ConfigParse.context( "The path of an !include must be a string." ).into() ),
+				None                          => continue,
+			};
+
+			let ( value, inner ) = self.include( &path, base )?;
+
+			if let Some( target ) = jptr_mut( &mut data, &ptr )
+			{
+				*target = value;
+			}
+
+			tags.remove( &ptr );
+			tags.nest  ( &ptr, inner );
+		}
+
+		Ok(( data, tags ))
+	}
+
+
+	fn include( &mut self, path: &str, base: Option< &Path > ) -> EkkeResult<( Value, Tags )>
+	{
+		let path = match base
+		{
+			Some( dir ) if Path::new( path ).is_relative() => dir.join( path ),
+			_                                              => PathBuf::from( path ),
+		};
+
+		let pattern = path.to_string_lossy();
+
+		if !pattern.contains( &[ '*', '?', '[' ][..] )
+		{
+			return self.file( &path );
+		}
+
+		let mut paths = glob( &pattern )
+
+			.context( format!( "Invalid glob pattern in !include: {}", pattern ) )?
+			.collect::< Result< Vec<_>, _ > >()
+			.context( format!( "Failed to read the files matching: {}", pattern ) )?
+		;
+
+		paths.sort();
+
+		let mut data = Document::from( Mapping::new() );
+
+		for path in paths.iter().filter( |p| p.is_file() )
+		{
+			match self.file( path )?
+			{
+				( Value::Mapping( map ), tags ) => data.merge( Document::from_parts( map.into(), tags ) )?,
+				( Value::Null          , _    ) => {}
+
+				_ => return Err( EkkeCfgError::ConfigParse.context
+				(
+					format!( "Files included with a glob pattern must contain mappings: {:?}", path )
+
+				).into() ),
+			}
+		}
+
+		Ok( data.into_parts() )
+	}
+
+
+	fn file( &mut self, path: &Path ) -> EkkeResult<( Value, Tags )>
+	{
+		let canonical = path.canonicalize().context( format!( "Failed to include: {:?}", path ) )?;
+
+		if let Some( index ) = self.stack.iter().position( |p| *p == canonical )
+		{
+			let mut cycle: Vec<String> = self.stack[ index.. ].iter().map( |p| p.to_string_lossy().into_owned() ).collect();
+			cycle.push( canonical.to_string_lossy().into_owned() );
+
+			return Err( EkkeCfgError::IncludeCycle( cycle.join( " -> " ) ).into() );
+		}
+
+		let ( value, tags ) = parse_tagged( &read_file( path ).context( format!( "{:?}", path ) )? )
+
+			.context( format!( "Failed to parse yaml at: {:?}", path ) )?
+		;
+
+		self.stack.push( canonical );
+		self.files.push( path.to_path_buf() );
+
+		let resolved = self.resolve( value, tags, path.parent() )?;
+
+		self.stack.pop();
+
+		Ok( resolved )
+	}
+}
@@ -0,0 +1,227 @@
+use crate :: { import::*, EkkeResult, EkkeCfgError, Pointer, pointer::escape, secret::{ is_sensitive, REDACTED } };
+
+
+// Resolve `${...}` references in the string values of the merged settings. A reference can be:
+//
+// - `${env:VAR}`: the environment variable VAR
+// - `${/my_app/data_dir}`: the value at a json pointer in the merged settings
+// - `${data_dir}`: the key data_dir in the same mapping as the string, or in the closest parent
+//   mapping that has it
+//
+// When the whole string is a single reference, it is replaced by the referenced value, whatever its
+// type. Otherwise the referenced values must be scalars and are formatted into the string. Use `$${`
+// to write a literal `${`.
+//
+// Strings at sensitive pointers are not repeated in error messages.
+//
+pub( crate ) fn interpolate( root: &Value, sensitive: &[ String ] ) -> EkkeResult< Value >
+{
+	Resolver{ root, sensitive, done: HashMap::new(), stack: Vec::new() }.resolve_ptr( "", "" )
+}
+
+
+// Escape every `${` in the strings of value as `$${`, so interpolating gives back the original strings.
+//
+pub( crate ) fn literal( value: Value ) -> Value
+{
+	match value
+	{
+		Value::String  ( s   ) => Value::String  ( s.replace( "${", "$${" ) ),
+		Value::Sequence( seq ) => Value::Sequence( seq.into_iter().map( literal ).collect() ),
+		Value::Mapping ( map ) => Value::Mapping ( map.into_iter().map( |( k, v )| ( k, literal( v ) ) ).collect() ),
+		other                  => other,
+	}
+}
+
+
+
+struct Resolver<'a>
+{
+	root     : &'a Value               ,
+	sensitive: &'a [ String ]          ,
+	done     : HashMap< String, Value >,
+	stack    : Vec< String >           ,
+}
+
+
+impl<'a> Resolver<'a>
+{
+	// Resolve the value at pointer. from is the pointer of the string referring to it, for error messages.
+	//
+	fn resolve_ptr( &mut self, ptr: &str, from: &str ) -> EkkeResult< Value >
+	{
+		if let Some( value ) = self.done.get( ptr )
+		{
+			return Ok( value.clone() );
+		}
+
+		if let Some( index ) = self.stack.iter().position( |p| p == ptr )
+		{
+			let mut cycle = self.stack[ index.. ].to_vec();
+			cycle.push( ptr.to_string() );
+
+			return Err( error( from, format!( "reference cycle: {}", cycle.join( " -> " ) ) ) );
+		}
+
+		let raw = match self.root.jptr( ptr )
+		{
+			Some( raw ) => raw,
+			None        => return Err( error( from, format!( "reference to a key that does not exist: {}", ptr ) ) ),
+		};
+
+		self.stack.push( ptr.to_string() );
+
+		let value = self.resolve_value( raw, ptr )?;
+
+		self.stack.pop();
+		self.done.insert( ptr.to_string(), value.clone() );
+
+		Ok( value )
+	}
+
+
+	fn resolve_value( &mut self, raw: &Value, ptr: &str ) -> EkkeResult< Value >
+	{
+		match raw
+		{
+			Value::String( s ) => self.resolve_string( s, ptr ),
+
+			Value::Mapping( map ) =>
+			{
+				let mut out = Mapping::new();
+
+				for ( key, value ) in map
+				{
+					let value = match key
+					{
+						Value::String( k ) => self.resolve_ptr  ( &format!( "{}/{}", ptr, escape( k ) ), ptr )?,
+						_                  => self.resolve_value( value, ptr                                 )?,
+					};
+
+					out.insert( key.clone(), value );
+				}
+
+				Ok( Value::Mapping( out ) )
+			}
+
+			Value::Sequence( seq ) =>
+			{
+				let mut out = Vec::with_capacity( seq.len() );
+
+				for i in 0..seq.len()
+				{
+					out.push( self.resolve_ptr( &format!( "{}/{}", ptr, i ), ptr )? );
+				}
+
+				Ok( Value::Sequence( out ) )
+			}
+
+			_ => Ok( raw.clone() ),
+		}
+	}
+
+
+	fn resolve_string( &mut self, input: &str, ptr: &str ) -> EkkeResult< Value >
+	{
+		let mut out  = String::new();
+		let mut rest = input;
+
+		while let Some( start ) = rest.find( '$' )
+		{
+			out.push_str( &rest[ ..start ] );
+			rest = &rest[ start.. ];
+
+			if rest.starts_with( "$${" )
+			{
+				out.push_str( "${" );
+				rest = &rest[ 3.. ];
+				continue;
+			}
+
+			if !rest.starts_with( "${" )
+			{
+				out.push( '$' );
+				rest = &rest[ 1.. ];
+				continue;
+			}
+
+			let end = rest.find( '}' ).ok_or_else( || error( ptr, format!( "unterminated reference in: {}", self.shown( input, ptr ) ) ) )?;
+			let value = self.reference( &rest[ 2..end ], ptr )?;
+
+			// The whole string is one reference, keep the type of the referenced value.
+			//
+			if out.is_empty() && end == rest.len() - 1 && rest.len() == input.len()
+			{
+				return Ok( value );
+			}
+
+			match value
+			{
+				Value::String( s ) => out.push_str( &s            ),
+				Value::Number( n ) => out.push_str( &n.to_string() ),
+				Value::Bool  ( b ) => out.push_str( &b.to_string() ),
+
+				_ => return Err( error( ptr, format!( "only strings, numbers and booleans can be formatted into a string, in: {}", self.shown( input, ptr ) ) ) ),
+			}
+
+			rest = &rest[ end+1.. ];
+		}
+
+		out.push_str( rest );
+
+		Ok( Value::String( out ) )
+	}
+
+
+	// The string at ptr as it can be shown in an error message.
+	//
+	fn shown<'s>( &self, input: &'s str, ptr: &str ) -> &'s str
+	{
+		if is_sensitive( ptr, self.sensitive ) { REDACTED } else { input }
+	}
+
+
+	// Look up the value of a single reference, without the `${}`.
+	//
+	fn reference( &mut self, name: &str, ptr: &str ) -> EkkeResult< Value >
+	{
+		if let Some( var ) = name.strip_prefix( "env:" )
+		{
+			return env::var( var )
+
+				.map( Value::String )
+				.map_err( |_| error( ptr, format!( "environment variable is not set: {}", var ) ) )
+			;
+		}
+
+		if name.starts_with( '/' )
+		{
+			return self.resolve_ptr( name, ptr );
+		}
+
+		// Look for the key in the mapping containing the string and then in its parents.
+		//
+		let mut scope = ptr;
+
+		while let Some( slash ) = scope.rfind( '/' )
+		{
+			scope = &scope[ ..slash ];
+
+			let candidate = format!( "{}/{}", scope, escape( name ) );
+
+			if self.root.jptr( &candidate ).is_some()
+			{
+				return self.resolve_ptr( &candidate, ptr );
+			}
+		}
+
+		Err( error( ptr, format!( "reference to a key that does not exist: {}", name ) ) )
+	}
+}
+
+
+
+fn error( pointer: &str, reason: String ) -> Error
+{
+	EkkeCfgError::Interpolation{ pointer: pointer.to_string(), reason }.into()
+}
@@ -0,0 +1,312 @@
+use crate :: { import::*, MergeStrategy, Pointer, pointer::{ remove_at, within }, secret::redact, strategy::{ merge_with, Strategies } };
+
+
+/// One level of configuration in a [`Config`](crate::Config). Layers are merged in order, so
+/// later layers override the values of earlier ones.
+///
+/// A layer has a name, which identifies it within the config. The names `default`, `userset`
+/// and `runtime` are used by ekke_config itself, see the associated constants.
+///
+/// A layer can lock values, so the layers above it can not change them. In yaml, tag the value
+/// with `!locked`, eg. `db_path: !locked /var/lib/my_app/db.sqlite`. Locking a mapping locks
+/// everything below it.
+///
+/// A layer can also remove values set by the layers below with `!unset`, eg. `log_file: !unset`, and choose
+/// how its values are merged with the ones below, eg. `primes: !append [ 11 ]`. See [`MergeStrategy`](crate::MergeStrategy).
+///
+/// The `Debug` output of the layers of a [`Config`](crate::Config) shows its sensitive values and the values of
+/// [`Secret`](crate::Secret) fields as `***`.
+///
+#[ derive( Clone, PartialEq, Eq, Deserialize ) ]
+//
+pub struct Layer
+{
+	name    :         String   ,
+	origin  : Option< String > ,
+	data    :         Mapping  ,
+
+	#[ serde( default ) ]
+	//
+	includes: Vec< PathBuf >   ,
+
+	#[ serde( default ) ]
+	//
+	locked  : Vec< String  >   ,
+
+	#[ serde( default ) ]
+	//
+	unset   : Vec< String  >   ,
+
+	#[ serde( default ) ]
+	//
+	directives: Strategies     ,
+
+	#[ serde( skip ) ]
+	//
+	hidden  : Hidden           ,
+}
+
+
+
+// The pointers of the values a layer hides in its Debug output, set by the config it belongs to. They
+// are not configuration data, so they don't affect equality.
+//
+#[ derive( Debug, Clone, Default ) ]
+//
+struct Hidden( Vec< String > );
+
+
+impl PartialEq for Hidden
+{
+	fn eq( &self, _other: &Self ) -> bool { true }
+}
+
+impl Eq for Hidden {}
+
+
+impl Layer
+{
+	/// The name of the layer holding the defaults shipped with the program.
+	///
+	pub const DEFAULT: &'static str = "default";
+
+	/// The name of the layer holding configuration set by the user, usually from a file in $HOME.
+	///
+	pub const USERSET: &'static str = "userset";
+
+	/// The name of the layer holding configuration added at runtime.
+	///
+	pub const RUNTIME: &'static str = "runtime";
+
+
+	/// Create a new layer.
+	///
+	pub fn new( name: impl Into<String>, data: Mapping ) -> Self
+	{
+		Self { name: name.into(), origin: None, data, includes: Vec::new(), locked: Vec::new(), unset: Vec::new(), directives: Strategies::new(), hidden: Hidden::default() }
+	}
+
+
+	/// Set where the data of this layer came from, eg. a file path or the name of an environment
+	/// variable prefix.
+	///
+	pub fn with_origin( mut self, origin: Option<String> ) -> Self
+	{
+		self.origin = origin;
+		self
+	}
+
+
+	/// The name of this layer.
+	///
+	pub fn name( &self ) -> &str
+	{
+		&self.name
+	}
+
+
+	/// Where the data of this layer came from, if known.
+	///
+	pub fn origin( &self ) -> Option< &str >
+	{
+		self.origin.as_deref()
+	}
+
+
+	/// The files included with `!include` in the data of this layer, in the order they where read.
+	///
+	pub fn includes( &self ) -> &[ PathBuf ]
+	{
+		&self.includes
+	}
+
+
+	/// The json pointers of the values this layer locks.
+	///
+	pub fn locked( &self ) -> &[ String ]
+	{
+		&self.locked
+	}
+
+
+	/// Lock the values at the given json pointers, so layers above this one can not override them.
+	///
+	pub fn with_locked( mut self, locked: Vec< String > ) -> Self
+	{
+		self.locked = locked;
+		self
+	}
+
+
+	/// The json pointers of the values this layer removes from the layers below.
+	///
+	pub fn unset( &self ) -> &[ String ]
+	{
+		&self.unset
+	}
+
+
+	/// Remove the values at the given json pointers from the layers below this one.
+	///
+	pub fn with_unset( mut self, unset: Vec< String > ) -> Self
+	{
+		self.unset = unset;
+		self
+	}
+
+
+	/// The merge strategies this layer chooses for its own values, by json pointer.
+	///
+	pub fn directives( &self ) -> &BTreeMap< String, MergeStrategy >
+	{
+		&self.directives
+	}
+
+
+	/// Choose how the values at the given json pointers are merged with the layers below.
+	///
+	pub fn with_directives( mut self, directives: BTreeMap< String, MergeStrategy > ) -> Self
+	{
+		self.directives = directives;
+		self
+	}
+
+
+	/// The (possibly incomplete) configuration data in this layer.
+	///
+	pub fn data( &self ) -> &Mapping
+	{
+		&self.data
+	}
+
+
+	/// Get a copy of the data as a `Value`, which allows lookup with [`Pointer`](crate::Pointer).
+	///
+	pub fn value( &self ) -> Value
+	{
+		self.data.clone().into()
+	}
+
+
+	pub( crate ) fn set_origin( &mut self, origin: Option<String> )
+	{
+		self.origin = origin;
+	}
+
+
+	pub( crate ) fn set_data( &mut self, data: Mapping )
+	{
+		self.data = data;
+	}
+
+
+	// Remove the value at ptr from this layer, together with the values it unsets and the directives
+	// at or below ptr. Locks are kept. Returns whether anything was removed.
+	//
+	pub( crate ) fn remove( &mut self, ptr: &str ) -> bool
+	{
+		let mut data = Value::Mapping( std::mem::take( &mut self.data ) );
+		let removed  = remove_at( &mut data, ptr ).is_some();
+
+		if let Value::Mapping( data ) = data
+		{
+			self.data = data;
+		}
+
+		let unset      = self.unset.len();
+		let directives = self.directives.len();
+
+		self.unset     .retain( |u| !within( u, ptr ) );
+		self.directives.retain( |d, _| !within( d, ptr ) );
+
+		removed || unset != self.unset.len() || directives != self.directives.len()
+	}
+
+
+	pub( crate ) fn with_includes( mut self, includes: Vec< PathBuf > ) -> Self
+	{
+		self.includes = includes;
+		self
+	}
+
+
+	// Hide the values at these pointers in the Debug output.
+	//
+	pub( crate ) fn hide( &mut self, hidden: Vec< String > )
+	{
+		self.hidden = Hidden( hidden );
+	}
+
+
+	// A copy with the values at sensitive pointers redacted.
+	//
+	pub( crate ) fn redacted( &self, sensitive: &[ String ] ) -> Self
+	{
+		let data = match redact( Value::Mapping( self.data.clone() ), sensitive )
+		{
+			Value::Mapping( data ) => data          ,
+			_                      => Mapping::new(),
+		};
+
+		Self { data, ..self.clone() }
+	}
+
+
+	// Merge another layer into this one, together with the files it included, its locks, the
+	// values it unsets and its directives. Unset values are removed from this layer as well. A
+	// value without a directive drops the directives of this layer for it.
+	//
+	pub( crate ) fn merge( &mut self, other: Layer, strategies: &Strategies ) -> MergeResult<()>
+	{
+		self.includes.extend( other.includes );
+
+		for ptr in other.locked
+		{
+			if !self.locked.contains( &ptr ) { self.locked.push( ptr ); }
+		}
+
+		let mut data = Value::Mapping( std::mem::take( &mut self.data ) );
+
+		for ptr in other.unset
+		{
+			remove_at( &mut data, &ptr );
+
+			if !self.unset.contains( &ptr ) { self.unset.push( ptr ); }
+		}
+
+		if let Value::Mapping( data ) = data
+		{
+			self.data = data;
+		}
+
+		let new        = Value::Mapping( other.data.clone() );
+		let directives = other.directives;
+
+		self.directives.retain( |ptr, _| new.jptr( ptr ).is_none() || directives.contains_key( ptr ) );
+
+		merge_with( &mut self.data, other.data, strategies, &directives, &[] )?;
+
+		self.directives.extend( directives );
+
+		Ok(())
+	}
+}
+
+
+
+impl Debug for Layer
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		f.debug_struct( "Layer" )
+
+			.field( "name"      , &self.name                             )
+			.field( "origin"    , &self.origin                           )
+			.field( "data"      , &self.redacted( &self.hidden.0 ).data  )
+			.field( "includes"  , &self.includes                         )
+			.field( "locked"    , &self.locked                           )
+			.field( "unset"     , &self.unset                            )
+			.field( "directives", &self.directives                       )
+			.finish()
+	}
+}
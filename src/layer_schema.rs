@@ -0,0 +1,184 @@
+use crate :: { import::*, EkkeResult, EkkeCfgError, Layer };
+
+#[ cfg( feature = "schema" ) ]
+//
+use crate :: { Violation, Violations, secret::covers_sensitive, source::read_file, yaml::parse };
+
+
+// A JSON Schema the layers of a config are checked against. It's not configuration data, so it doesn't
+// affect equality. Without the `schema` feature, there is never a schema.
+//
+#[ derive( Clone, Default ) ]
+//
+pub( crate ) struct LayerSchema
+{
+	#[ cfg( feature = "schema" ) ]
+	//
+	schema: Option< Arc< JSONSchema > >,
+}
+
+
+impl LayerSchema
+{
+	#[ cfg( feature = "schema" ) ]
+	//
+	pub( crate ) fn compile( schema: Option< &JsonValue > ) -> EkkeResult< Self >
+	{
+		let schema = match schema
+		{
+			Some( schema ) => schema,
+			None           => return Ok( Self::default() ),
+		};
+
+		let compiled = JSONSchema::compile( schema )
+
+			.map_err( |e| EkkeCfgError::ConfigParse.context( format!( "Invalid JSON Schema: {}", e ) ) )?
+		;
+
+		Ok( Self { schema: Some( Arc::new( compiled ) ) } )
+	}
+
+
+	// Read a schema from a json or yaml file.
+	//
+	#[ cfg( feature = "schema" ) ]
+	//
+	pub( crate ) fn from_file( path: &Path ) -> EkkeResult< Self >
+	{
+		let schema = parse( &read_file( path ).context( format!( "{:?}", path ) )? )
+
+			.context( format!( "Failed to parse JSON Schema at: {:?}", path ) )?
+		;
+
+		let schema = serde_json::to_value( schema ).context( format!( "Failed to parse JSON Schema at: {:?}", path ) )?;
+
+		Ok( Self::compile( Some( &schema ) ).context( format!( "{:?}", path ) )? )
+	}
+
+
+	#[ cfg( not( feature = "schema" ) ) ]
+	//
+	pub( crate ) fn from_file( path: &Path ) -> EkkeResult< Self >
+	{
+		Err( EkkeCfgError::ConfigParse.context( format!( "Checking layers against the JSON Schema at {:?} requires the schema feature", path ) ).into() )
+	}
+
+
+	// Check every layer against the schema and fail with all violations. Layers above the defaults
+	// only override some values, so missing required properties of objects are only reported for the
+	// default layer. Elements of sequences and entries of maps must still be complete, as they don't
+	// merge with the ones below. Null values are left alone as well, since they remove a value from
+	// the layers below.
+	// The messages for sensitive values, and for the values that contain them, are redacted, as they show the value.
+	//
+	#[ cfg( feature = "schema" ) ]
+	//
+	pub( crate ) fn check( &self, layers: &[ Layer ], sensitive: &[ String ] ) -> EkkeResult<()>
+	{
+		let schema = match &self.schema
+		{
+			Some( schema ) => schema,
+			None           => return Ok(()),
+		};
+
+		let mut violations = Violations::default();
+
+		for layer in layers
+		{
+			let data = serde_json::to_value( layer.data() )
+
+				.context( format!( "Failed to convert layer {} to json", layer.name() ) )?
+			;
+
+			let errors = match schema.validate( &data )
+			{
+				Ok (()    ) => continue,
+				Err( errors ) => errors,
+			};
+
+			let partial = layer.name() != Layer::DEFAULT;
+
+			for error in errors
+			{
+				let required = matches!( error.kind, ValidationErrorKind::Required{ .. } ) && !in_collection( &error.schema_path.to_string() );
+
+				if partial && ( required || error.instance.is_null() ) { continue }
+
+				violations.0.push( Violation
+				{
+					pointer: error.instance_path.to_string()   ,
+					message: error.to_string()                 ,
+					layer  : Some( layer.name().to_string() )  ,
+					origin : layer.origin().map( String::from ),
+				});
+			}
+		}
+
+		if violations.is_empty() { return Ok(()) }
+
+		violations.redact( |ptr| covers_sensitive( ptr, sensitive ) );
+
+		Err( EkkeCfgError::Invalid( violations ).into() )
+	}
+
+
+	#[ cfg( not( feature = "schema" ) ) ]
+	//
+	pub( crate ) fn check( &self, _layers: &[ Layer ], _sensitive: &[ String ] ) -> EkkeResult<()>
+	{
+		Ok(())
+	}
+}
+
+
+impl Debug for LayerSchema
+{
+	#[ cfg( feature = "schema" ) ]
+	//
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "LayerSchema({})", if self.schema.is_some() { "Some" } else { "None" } )
+	}
+
+
+	#[ cfg( not( feature = "schema" ) ) ]
+	//
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "LayerSchema(None)" )
+	}
+}
+
+
+impl PartialEq for LayerSchema
+{
+	fn eq( &self, _other: &Self ) -> bool { true }
+}
+
+impl Eq for LayerSchema {}
+
+
+
+// Whether a schema path goes through the schema of sequence elements or map entries. The names of
+// properties and definitions are skipped, so a property called `items` does not count.
+//
+#[ cfg( feature = "schema" ) ]
+//
+fn in_collection( schema_path: &str ) -> bool
+{
+	let mut keywords = schema_path.split( '/' ).skip( 1 );
+
+	while let Some( keyword ) = keywords.next()
+	{
+		match keyword
+		{
+			"properties" | "definitions" | "$defs" | "dependencies" => { keywords.next(); }
+
+			"items" | "additionalItems" | "additionalProperties" | "patternProperties" | "contains" => return true,
+
+			_ => {}
+		}
+	}
+
+	false
+}
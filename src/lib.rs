@@ -16,24 +16,161 @@
 //! - currently only works with serde_yaml
 //! - no configuration profiles (debug, production, staging...) -> use different config files for these for now.
 //!
+//! Features:
+//! - `stream`: async notification of configuration updates on a [`SharedConfig`] through `ConfigStream`
+//!   and `ConfigWatch`. Pulls in futures.
+//! - `schema`: JSON Schemas for configuration files with `Config::schema` and `Config::userset_schema`,
+//!   and validating layers against a JSON Schema with `Config::set_schema`. Pulls in schemars, jsonschema
+//!   and serde_json.
+//!
+//! For more control over where configuration comes from, [`ConfigBuilder`] merges any number of named
+//! sources, like files, directories, environment variables and command line options.
+//!
 //! See examples/basic.rs for an introductory example.
 //!
 
+mod builder;
 mod config;
+mod crypt;
 mod error;
+mod expand;
+mod history;
+mod include;
+mod interpolate;
+mod layer;
+mod layer_schema;
+mod lock;
+mod patch;
 mod pointer;
+mod policy;
+mod secret;
+mod shared;
+mod source;
+mod strategy;
+mod tags;
+mod transaction;
+mod unset;
+mod validate;
+mod xdg;
+mod yaml;
+
+#[ cfg( feature = "schema" ) ]
+//
+mod schema;
+
+#[ cfg( feature = "stream" ) ]
+//
+mod stream;
 
 
+pub use builder::
+{
+	ConfigBuilder ,
+};
+
 pub use config::
 {
 	Config ,
 };
 
+pub use crypt::
+{
+	EncryptionKey ,
+	EncryptedTag  ,
+};
+
+pub use history::
+{
+	Change ,
+};
+
+pub use layer::
+{
+	Layer ,
+};
+
 pub use pointer::
 {
 	Pointer ,
 };
 
+pub use policy::
+{
+	FilePolicy ,
+};
+
+pub use secret::
+{
+	Secret ,
+};
+
+pub use shared::
+{
+	SharedConfig ,
+};
+
+pub use source::
+{
+	Source      ,
+	FileSource  ,
+	DirSource   ,
+	StrSource   ,
+	EnvSource   ,
+	CliSource   ,
+	ValueSource ,
+};
+
+pub use strategy::
+{
+	MergeStrategy ,
+};
+
+pub use tags::
+{
+	TagResolver ,
+	EnvTag      ,
+	FileTag     ,
+	CmdTag      ,
+};
+
+pub use transaction::
+{
+	Transaction ,
+};
+
+pub use validate::
+{
+	Validate   ,
+	Validator  ,
+	Violation  ,
+	Violations ,
+};
+
+pub use xdg::
+{
+	XdgDirs ,
+};
+
+pub use yaml::
+{
+	Document ,
+};
+
+#[ cfg( feature = "schema" ) ]
+//
+pub use schemars::
+{
+	JsonSchema ,
+};
+
+#[ cfg( feature = "stream" ) ]
+//
+pub use stream::
+{
+	ConfigStream ,
+	ConfigWatch  ,
+};
+
 
 pub use error::
 {
@@ -48,12 +185,36 @@ mod import
 	//
 	pub( crate ) use
 	{
+		arc_swap    :: { ArcSwap                                                                                    } ,
 		failure     :: { Error, Fail, ResultExt                                                                     } ,
+		glob        :: { glob                                                                                       } ,
+		regex       :: { Regex                                                                                      } ,
 		std         :: { convert::TryFrom, fs::File, io::BufReader, io::Read, path::Path, path::PathBuf, fmt::Debug } ,
-		serde       :: { ser::Serialize, Deserialize,  de::DeserializeOwned                                         } ,
+		std         :: { fmt, cell::RefCell, ops::RangeBounds                                                       } ,
+		std         :: { env, fs, ffi::OsString, collections::{ BTreeMap, HashMap, VecDeque }, process::Command     } ,
+		std         :: { sync::{ Arc, Mutex, PoisonError }                                                          } ,
+		serde       :: { ser::Serialize, Serializer, Deserialize, Deserializer, de::DeserializeOwned                } ,
 		serde_yaml  :: { Value, Mapping, from_str                                                                   } ,
-		shellexpand :: { tilde                                                                                      } ,
+		shellexpand :: { tilde, env_with_context                                                                    } ,
 
 		ekke_merge  :: { Merge, MergeResult                                                                         } ,
 	};
+
+	#[ cfg( feature = "schema" ) ]
+	//
+	pub( crate ) use
+	{
+		jsonschema  :: { JSONSchema, error::ValidationErrorKind                                                     } ,
+		schemars    :: { JsonSchema, gen::{ SchemaGenerator, SchemaSettings }, schema::Schema                       } ,
+		serde_json  :: { Value as JsonValue                                                                         } ,
+	};
+
+	#[ cfg( feature = "stream" ) ]
+	//
+	pub( crate ) use
+	{
+		futures     :: { Stream, StreamExt, channel::mpsc::{ unbounded, UnboundedReceiver, UnboundedSender }        } ,
+		futures     :: { task::AtomicWaker                                                                          } ,
+		std         :: { pin::Pin, sync::Weak, task::{ Context, Poll }                                              } ,
+	};
 }
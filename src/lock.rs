@@ -0,0 +1,145 @@
+use crate :: { import::*, EkkeResult, EkkeCfgError, Layer, MergeStrategy, pointer::{ unescape, within }, strategy::Strategies, yaml::Tags };
+
+
+// The tag to lock a value, so layers above can not override it.
+//
+pub( crate ) const LOCKED: &str = "!locked";
+
+
+// Take the `!locked` tags out. Returns the pointers of the locked values.
+//
+pub( crate ) fn extract_locks( tags: &mut Tags ) -> Vec< String >
+{
+	tags.take( LOCKED )
+}
+
+
+// Fail if data, unsetting values or replacing a parent with the `!replace` directive would override a value
+// locked by one of the layers.
+//
+pub( crate ) fn check_locks( layers: &[ Layer ], data: &Mapping, unset: &[ String ], directives: &Strategies ) -> EkkeResult<()>
+{
+	for layer in layers
+	{
+		let locked = |ptr: &&String| overrides( data, ptr ) || unset.iter().any( |u| related( u, ptr ) ) || replaces( directives, ptr );
+
+		if let Some( ptr ) = layer.locked().iter().find( locked )
+		{
+			return Err( EkkeCfgError::Locked{ pointer: ptr.clone(), layer: layer.name().to_string() }.into() );
+		}
+	}
+
+	Ok(())
+}
+
+
+// The data and unset pointers of each layer, without the ones that override values locked by the
+// layers below. Also returns a warning for every value that was dropped. A layer that replaces the
+// parent of a locked value gets a warning as well. Merging keeps the locked value, see `merge_with`.
+//
+pub( crate ) fn unlocked_data( layers: &[ Layer ] ) -> ( Vec<( Mapping, Vec< String > )>, Vec< String > )
+{
+	let mut locks    = Vec::<( String, String )>::new();
+	let mut out      = Vec::with_capacity( layers.len() );
+	let mut warnings = Vec::new();
+
+	for layer in layers
+	{
+		let mut data  = layer.data().clone();
+		let mut unset = layer.unset().to_vec();
+
+		for ( ptr, owner ) in &locks
+		{
+			let len = unset.len();
+
+			unset.retain( |u| !related( u, ptr ) );
+
+			if strip( &mut data, ptr ) || unset.len() != len || replaces( layer.directives(), ptr )
+			{
+				warnings.push( format!( "Layer {} tries to override {}, which is locked by layer {}", layer.name(), ptr, owner ) );
+			}
+		}
+
+		out.push(( data, unset ));
+
+		locks.extend( layer.locked().iter().map( |ptr| ( ptr.clone(), layer.name().to_string() ) ) );
+	}
+
+	( out, warnings )
+}
+
+
+// Whether merging data would change the value at ptr: data has a value at ptr or below it, or it
+// replaces one of the parents with something that is not a mapping.
+//
+fn overrides( data: &Mapping, ptr: &str ) -> bool
+{
+	let mut map = data;
+
+	for token in ptr.split( '/' ).skip( 1 )
+	{
+		match map.get( &unescape( token ).into() )
+		{
+			None                          => return false,
+			Some( Value::Mapping( next ) ) => map = next  ,
+			Some( _ )                     => return true ,
+		}
+	}
+
+	true
+}
+
+
+// Whether one of the directives replaces the value at ptr or one of its parents.
+//
+fn replaces( directives: &Strategies, ptr: &str ) -> bool
+{
+	directives.iter().any( |( dir, strategy )| *strategy == MergeStrategy::Replace && within( ptr, dir ) )
+}
+
+
+// Whether two pointers are the same, or one is below the other.
+//
+fn related( a: &str, b: &str ) -> bool
+{
+	let below = |a: &str, b: &str| a.len() > b.len() && a.starts_with( b ) && a[ b.len().. ].starts_with( '/' );
+
+	a == b || below( a, b ) || below( b, a )
+}
+
+
+// Remove whatever overrides the value at ptr from data. Returns whether anything was removed.
+//
+fn strip( data: &mut Mapping, ptr: &str ) -> bool
+{
+	let tokens: Vec< Value > = ptr.split( '/' ).skip( 1 ).map( |t| unescape( t ).into() ).collect();
+
+	let ( last, parents ) = match tokens.split_last()
+	{
+		Some( split ) => split,
+		None          => return false,
+	};
+
+	let mut map = data;
+
+	for token in parents
+	{
+		match map.get( token )
+		{
+			None                     => return false,
+			Some( Value::Mapping(_) ) => {}
+
+			// A parent that is not a mapping replaces the locked value entirely.
+			//
+			Some( _ ) => return map.remove( token ).is_some(),
+		}
+
+		map = match map.get_mut( token )
+		{
+			Some( Value::Mapping( next ) ) => next,
+			_                              => return false,
+		};
+	}
+
+	map.remove( last ).is_some()
+}
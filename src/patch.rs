@@ -0,0 +1,176 @@
+use crate :: { import::*, EkkeResult, EkkeCfgError, Pointer, pointer::{ insert_at, jptr_mut, remove_at } };
+
+
+// An operation of a json patch, see RFC 6902.
+//
+#[ derive( Debug, Clone, Deserialize ) ]
+#[ serde( tag = "op", rename_all = "lowercase" ) ]
+//
+enum Operation
+{
+	Add     { path: String, value: Value },
+	Remove  { path: String               },
+	Replace { path: String, value: Value },
+	Move    { from: String, path : String },
+	Copy    { from: String, path : String },
+	Test    { path: String, value: Value },
+}
+
+
+impl Operation
+{
+	// The pointers this operation changes.
+	//
+	fn touched( &self ) -> Vec< &str >
+	{
+		match self
+		{
+			Self::Move{ from, path } => vec![ from, path ],
+			Self::Test{ .. }         => Vec::new()        ,
+
+			Self::Add    { path, .. } |
+			Self::Remove { path     } |
+			Self::Replace{ path, .. } |
+			Self::Copy   { path, .. } => vec![ path ],
+		}
+	}
+
+
+	fn apply( self, doc: &mut Value ) -> Result< (), String >
+	{
+		match self
+		{
+			Self::Add{ path, value } => add( doc, &path, value ),
+
+			Self::Remove{ path } => remove_at( doc, &path ).map( drop ).ok_or_else( || format!( "there is no value at {}", path ) ),
+
+			Self::Replace{ path, value } => match jptr_mut( doc, &path )
+			{
+				Some( old ) => { *old = value; Ok(()) }
+				None        => Err( format!( "there is no value at {}", path ) ),
+			}
+
+			Self::Move{ from, path } =>
+			{
+				if path.starts_with( &format!( "{}/", from ) )
+				{
+					return Err( format!( "can not move {} into itself", from ) );
+				}
+
+				let value = remove_at( doc, &from ).ok_or_else( || format!( "there is no value at {}", from ) )?;
+
+				add( doc, &path, value )
+			}
+
+			Self::Copy{ from, path } =>
+			{
+				let value = doc.jptr( &from ).cloned().ok_or_else( || format!( "there is no value at {}", from ) )?;
+
+				add( doc, &path, value )
+			}
+
+			Self::Test{ path, value } => match doc.jptr( &path )
+			{
+				Some( actual ) if *actual == value => Ok(()),
+				Some( _      )                     => Err( format!( "the value at {} is different", path ) ),
+				None                               => Err( format!( "there is no value at {}", path ) ),
+			}
+		}
+	}
+}
+
+
+fn add( doc: &mut Value, path: &str, value: Value ) -> Result< (), String >
+{
+	insert_at( doc, path, value ).ok_or_else( || format!( "can not add a value at {}", path ) )
+}
+
+
+
+// A parsed json patch.
+//
+pub( crate ) struct JsonPatch( Vec< Operation > );
+
+
+impl JsonPatch
+{
+	pub( crate ) fn parse( patch: Value ) -> EkkeResult< Self >
+	{
+		let ops = match patch
+		{
+			Value::Sequence( ops ) => ops,
+			_                      => return Err( EkkeCfgError::ConfigParse.context( "A json patch must be a list of operations" ).into() ),
+		};
+
+		let ops = ops.into_iter().enumerate().map( |( index, op )|
+		{
+			serde_yaml::from_value( op ).map_err( |e| EkkeCfgError::Patch{ index, reason: e.to_string() } )
+
+		}).collect::< Result< _, _ > >()?;
+
+		Ok( Self( ops ) )
+	}
+
+
+	// The pointers the patch changes.
+	//
+	pub( crate ) fn touched( &self ) -> Vec< String >
+	{
+		self.0.iter().flat_map( Operation::touched ).map( String::from ).collect()
+	}
+
+
+	// Apply the operations in order. Stops at the first one that fails, so doc might be partly patched.
+	//
+	pub( crate ) fn apply( self, doc: &mut Value ) -> EkkeResult<()>
+	{
+		for ( index, op ) in self.0.into_iter().enumerate()
+		{
+			op.apply( doc ).map_err( |reason| EkkeCfgError::Patch{ index, reason } )?;
+		}
+
+		Ok(())
+	}
+}
+
+
+
+// Apply a json merge patch, see RFC 7396.
+//
+pub( crate ) fn merge_patch( doc: &mut Value, patch: &Value )
+{
+	let patch = match patch
+	{
+		Value::Mapping( patch ) => patch,
+		_                       => { *doc = patch.clone(); return; }
+	};
+
+	if !doc.is_mapping()
+	{
+		*doc = Value::Mapping( Mapping::new() );
+	}
+
+	if let Value::Mapping( map ) = doc
+	{
+		for ( key, value ) in patch
+		{
+			if value.is_null()
+			{
+				map.remove( key );
+				continue;
+			}
+
+			match map.get_mut( key )
+			{
+				Some( old ) => merge_patch( old, value ),
+
+				None =>
+				{
+					let mut new = Value::Null;
+					merge_patch( &mut new, value );
+					map.insert( key.clone(), new );
+				}
+			}
+		}
+	}
+}
@@ -12,17 +12,6 @@ impl Pointer for Value
 	///
 	fn jptr<'a>( &'a self, pointer: &str ) -> Option< &'a Value >
 	{
-		fn parse_index( s: &str ) -> Option< usize >
-		{
-			if s.starts_with( '+' ) || ( s.starts_with( '0' ) && s.len() != 1 )
-			{
-				return None;
-			}
-
-			s.parse().ok()
-		}
-
-
 		if  pointer == ""              { return Some( self ) }
 		if !pointer.starts_with( '/' ) { return None               }
 
@@ -31,7 +20,7 @@ impl Pointer for Value
 
 		for escaped_token in pointer.split( '/' ).skip(1)
 		{
-			let token = escaped_token.replace( "~0", "~" ).replace( "~1", "/" );
+			let token = unescape( escaped_token );
 
 
 			let target_opt = match *target
@@ -54,6 +43,130 @@ impl Pointer for Value
 }
 
 
+// Like `Pointer::jptr`, but returns a mutable reference.
+//
+pub( crate ) fn jptr_mut<'a>( root: &'a mut Value, pointer: &str ) -> Option< &'a mut Value >
+{
+	if  pointer.is_empty()         { return Some( root ) }
+	if !pointer.starts_with( '/' ) { return None         }
+
+	let mut target = root;
+
+	for escaped_token in pointer.split( '/' ).skip(1)
+	{
+		let token = unescape( escaped_token );
+
+		target = match target
+		{
+			Value::Mapping ( map  ) => map.get_mut( &token.into() )?,
+			Value::Sequence( list ) => list.get_mut( parse_index( &token )? )?,
+			_                       => return None,
+		};
+	}
+
+	Some( target )
+}
+
+
+// Escape a key for use in a json pointer.
+//
+pub( crate ) fn escape( key: &str ) -> String
+{
+	key.replace( '~', "~0" ).replace( '/', "~1" )
+}
+
+
+pub( crate ) fn unescape( token: &str ) -> String
+{
+	token.replace( "~1", "/" ).replace( "~0", "~" )
+}
+
+
+// Whether pointer is parent, or points below it.
+//
+pub( crate ) fn within( pointer: &str, parent: &str ) -> bool
+{
+	pointer == parent || ( pointer.starts_with( parent ) && pointer[ parent.len().. ].starts_with( '/' ) )
+}
+
+
+// Remove the value at pointer from root. Returns the removed value.
+//
+pub( crate ) fn remove_at( root: &mut Value, pointer: &str ) -> Option< Value >
+{
+	let ( parent, token ) = split( pointer )?;
+
+	match jptr_mut( root, parent )?
+	{
+		Value::Mapping ( map  ) => map.remove( &token.into() ),
+
+		Value::Sequence( list ) =>
+		{
+			let index = parse_index( &token ).filter( |i| *i < list.len() )?;
+
+			Some( list.remove( index ) )
+		}
+
+		_ => None,
+	}
+}
+
+
+// Set the value at pointer, the way the `add` operation of json patch does: a mapping entry is
+// inserted or replaced, a value is inserted in a sequence, or appended for the index `-`. The
+// parent must exist. Returns None if the value could not be added.
+//
+pub( crate ) fn insert_at( root: &mut Value, pointer: &str, value: Value ) -> Option<()>
+{
+	if pointer.is_empty()
+	{
+		*root = value;
+		return Some(());
+	}
+
+	let ( parent, token ) = split( pointer )?;
+
+	match jptr_mut( root, parent )?
+	{
+		Value::Mapping( map ) => { map.insert( token.into(), value ); }
+
+		Value::Sequence( list ) if token == "-" => list.push( value ),
+
+		Value::Sequence( list ) =>
+		{
+			let index = parse_index( &token ).filter( |i| *i <= list.len() )?;
+
+			list.insert( index, value );
+		}
+
+		_ => return None,
+	}
+
+	Some(())
+}
+
+
+// Split a pointer in the pointer of the parent and the unescaped last token.
+//
+fn split( pointer: &str ) -> Option<( &str, String )>
+{
+	let index = pointer.rfind( '/' )?;
+
+	Some(( &pointer[ ..index ], unescape( &pointer[ index+1.. ] ) ))
+}
+
+
+fn parse_index( s: &str ) -> Option< usize >
+{
+	if s.starts_with( '+' ) || ( s.starts_with( '0' ) && s.len() != 1 )
+	{
+		return None;
+	}
+
+	s.parse().ok()
+}
+
+
 /*
 impl Pointer for Mapping
 {
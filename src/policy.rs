@@ -0,0 +1,264 @@
+use crate :: { import::*, EkkeResult, EkkeCfgError };
+
+
+thread_local!
+{
+	static ACTIVE: RefCell< Option< FilePolicy > > = const { RefCell::new( None ) };
+}
+
+
+/// Checks on the permissions and owner of every file ekke_config reads: configuration files, included
+/// files, `!file` secrets and key files. A file that can be changed by others than the expected users
+/// could be used to take over a program running with more privileges, like a setuid helper or a daemon.
+///
+/// By default, a policy refuses files that are group or world writable, and files that are not owned by
+/// root or the effective user. The directories the file is in are held to the same rules, as whoever can
+/// write to them can replace the file. Directories with the sticky bit, like `/tmp`, may be writable and
+/// directories may always be owned by root. A violation is reported as [`EkkeCfgError::FilePolicy`], or
+/// passed to a warning function when one is set with [`FilePolicy::warn_with`].
+///
+/// Policies are opt-in. Set one with [`ConfigBuilder::file_policy`](crate::ConfigBuilder::file_policy),
+/// [`Config::set_file_policy`](crate::Config::set_file_policy) or [`FilePolicy::apply`]:
+///
+/// ```no_run
+/// # use ekke_config::{ Config, FilePolicy };
+/// # use std::{ convert::TryFrom, path::Path };
+/// # #[ derive( serde::Serialize, serde::Deserialize, Debug, Clone ) ] struct Settings {}
+/// #
+/// let policy = FilePolicy::new();
+///
+/// let mut config: Config<Settings> = policy.apply( || Config::try_from( Path::new( "/usr/share/my_app/defaults.yml" ) ) )?;
+///
+/// // Keep checking files on reload.
+/// //
+/// config.set_file_policy( Some( policy ) );
+/// # Ok::<(), failure::Error>(())
+/// ```
+///
+/// The checks only apply on unix. Elsewhere all files pass.
+///
+#[ derive( Debug, Clone ) ]
+//
+pub struct FilePolicy
+{
+	group_writable:         bool                 ,
+	world_writable:         bool                 ,
+	owners        : Option< Vec< u32 >         > ,
+	warn          : Option< fn( &EkkeCfgError ) > ,
+}
+
+
+impl Default for FilePolicy
+{
+	fn default() -> Self
+	{
+		Self::new()
+	}
+}
+
+
+impl FilePolicy
+{
+	/// The default policy: no group or world writable files, owned by root or the effective user.
+	///
+	pub fn new() -> Self
+	{
+		Self
+		{
+			group_writable: false                      ,
+			world_writable: false                      ,
+			owners        : Some( vec![ 0, euid() ] )  ,
+			warn          : None                       ,
+		}
+	}
+
+
+	/// Whether files may be writable by their group. Defaults to false.
+	///
+	pub fn group_writable( mut self, allow: bool ) -> Self
+	{
+		self.group_writable = allow;
+		self
+	}
+
+
+	/// Whether files may be writable by everyone. Defaults to false.
+	///
+	pub fn world_writable( mut self, allow: bool ) -> Self
+	{
+		self.world_writable = allow;
+		self
+	}
+
+
+	/// The user ids that may own files. None allows any owner. Defaults to root and the effective user.
+	///
+	pub fn owners( mut self, uids: Option< Vec< u32 > > ) -> Self
+	{
+		self.owners = uids;
+		self
+	}
+
+
+	/// Only warn about violations. Instead of refusing the file, `warn` is called with the
+	/// violation and the file is read anyway.
+	///
+	pub fn warn_with( mut self, warn: fn( &EkkeCfgError ) ) -> Self
+	{
+		self.warn = Some( warn );
+		self
+	}
+
+
+	/// Check a file against this policy.
+	///
+	pub fn check( &self, path: &Path ) -> EkkeResult<()>
+	{
+		self.open( path ).map( drop )
+	}
+
+
+	// Open a file and check it against this policy. The file itself is checked through the open handle,
+	// so it can not be swapped for another one between the check and reading it.
+	//
+	fn open( &self, path: &Path ) -> EkkeResult< File >
+	{
+		let file = File::open( path ).context( format!( "{:?}", path ) )?;
+
+		let violation = match self.violation( path, &file )?
+		{
+			Some( reason ) => EkkeCfgError::FilePolicy{ path: path.to_string_lossy().into_owned(), reason },
+			None           => return Ok( file ),
+		};
+
+		match self.warn
+		{
+			Some( warn ) => { warn( &violation ); Ok( file ) }
+			None         => Err( violation.into() ),
+		}
+	}
+
+
+	/// Run `f` with this policy applied to every file ekke_config reads on the current thread.
+	///
+	pub fn apply<R>( &self, f: impl FnOnce() -> EkkeResult<R> ) -> EkkeResult<R>
+	{
+		let previous = ACTIVE.with( |active| active.replace( Some( self.clone() ) ) );
+
+		// Restore the previous policy, even if f panics.
+		//
+		struct Restore( Option< FilePolicy > );
+
+		impl Drop for Restore
+		{
+			fn drop( &mut self )
+			{
+				ACTIVE.with( |active| *active.borrow_mut() = self.0.take() );
+			}
+		}
+
+		let _restore = Restore( previous );
+
+		f()
+	}
+
+
+	#[ cfg( unix ) ]
+	//
+	fn violation( &self, path: &Path, file: &File ) -> EkkeResult< Option< String > >
+	{
+		let meta = file.metadata().context( format!( "{:?}", path ) )?;
+
+		if let Some( reason ) = self.reason( &meta, false )
+		{
+			return Ok( Some( format!( "it {}", reason ) ) );
+		}
+
+		// Symlinks are resolved, so these are the directories of the file that was opened.
+		//
+		let real = fs::canonicalize( path ).context( format!( "{:?}", path ) )?;
+
+		for dir in real.ancestors().skip( 1 )
+		{
+			let meta = fs::metadata( dir ).context( format!( "{:?}", dir ) )?;
+
+			if let Some( reason ) = self.reason( &meta, true )
+			{
+				return Ok( Some( format!( "its directory {:?} {}", dir, reason ) ) );
+			}
+		}
+
+		Ok( None )
+	}
+
+
+	// Why a file or directory breaks the policy, if it does.
+	//
+	#[ cfg( unix ) ]
+	//
+	fn reason( &self, meta: &fs::Metadata, dir: bool ) -> Option< String >
+	{
+		use std::os::unix::fs::MetadataExt;
+
+		let mode   = meta.mode();
+		let sticky = dir && mode & 0o1000 != 0;
+
+		if !self.world_writable && !sticky && mode & 0o002 != 0
+		{
+			return Some( format!( "is world writable (mode {:o})", mode & 0o7777 ) );
+		}
+
+		if !self.group_writable && !sticky && mode & 0o020 != 0
+		{
+			return Some( format!( "is group writable (mode {:o})", mode & 0o7777 ) );
+		}
+
+		match &self.owners
+		{
+			Some( owners ) if !owners.contains( &meta.uid() ) && ( !dir || meta.uid() != 0 ) =>
+
+				Some( format!( "is owned by uid {}, expected one of {:?}", meta.uid(), owners ) ),
+
+			_ => None,
+		}
+	}
+
+
+	#[ cfg( not( unix ) ) ]
+	//
+	fn violation( &self, _path: &Path, _file: &File ) -> EkkeResult< Option< String > >
+	{
+		Ok( None )
+	}
+}
+
+
+
+// Open a file, checking it against the policy that is active on this thread, if any.
+//
+pub( crate ) fn open( path: &Path ) -> EkkeResult< File >
+{
+	match ACTIVE.with( |active| active.borrow().clone() )
+	{
+		Some( policy ) => policy.open( path ),
+		None           => Ok( File::open( path )? ),
+	}
+}
+
+
+#[ cfg( unix ) ]
+//
+fn euid() -> u32
+{
+	// Safe: geteuid has no preconditions and can not fail.
+	//
+	unsafe { libc::geteuid() }
+}
+
+
+#[ cfg( not( unix ) ) ]
+//
+fn euid() -> u32
+{
+	0
+}
@@ -0,0 +1,130 @@
+use crate :: { import::*, Config, EkkeResult, Layer, pointer::escape, secret::{ is_sensitive, secret_pointers } };
+
+
+/// Generate [JSON Schema](https://json-schema.org)s for configuration files, eg. for editors or an admin panel. Requires
+/// the `schema` feature. The settings type must implement [`JsonSchema`](schemars::JsonSchema), which can be derived
+/// with [schemars](https://docs.rs/schemars). Doc comments on the type and its fields end up as descriptions.
+///
+/// ```
+/// # use ekke_config::Config;
+/// # use std::convert::TryFrom;
+/// #
+/// #[ derive( serde::Serialize, serde::Deserialize, schemars::JsonSchema, Debug, Clone ) ]
+/// //
+/// struct Settings
+/// {
+///    /// How much to log.
+///    //
+///    log_lvl: String,
+/// }
+///
+/// let config: Config<Settings> = Config::try_from( "default: { log_lvl: warn }" )?;
+///
+/// let schema = config.userset_schema()?;
+///
+/// assert_eq!( schema[ "properties" ][ "log_lvl" ][ "default"     ], "warn"           );
+/// assert_eq!( schema[ "properties" ][ "log_lvl" ][ "description" ], "How much to log." );
+/// # Ok::<(), failure::Error>(())
+/// ```
+///
+impl<T> Config<T> where T: Clone + DeserializeOwned + Serialize + Debug + JsonSchema
+{
+	/// The schema of a complete configuration, like the settings under the `default` key of a defaults file. The values
+	/// of the default layer are given as defaults, except for sensitive ones.
+	///
+	pub fn schema( &self ) -> EkkeResult< JsonValue >
+	{
+		self.make_schema( false )
+	}
+
+
+	/// The schema of a userset file. Like [`Config::schema`], but no property is required, since a userset file only
+	/// overrides some of the defaults. Elements of sequences and entries of maps must still be complete.
+	///
+	pub fn userset_schema( &self ) -> EkkeResult< JsonValue >
+	{
+		self.make_schema( true )
+	}
+
+
+	fn make_schema( &self, partial: bool ) -> EkkeResult< JsonValue >
+	{
+		let defaults = match self.layer( Layer::DEFAULT )
+		{
+			Some( layer ) => Some( serde_json::to_value( layer.data() ).context( "Failed to convert the defaults to json" )? ),
+			None          => None,
+		};
+
+		let generator = SchemaSettings::draft07().with( |s| s.inline_subschemas = true ).into_generator();
+
+		let mut schema = serde_json::to_value( generator.into_root_schema_for::<T>() )?;
+
+		// Secret fields serialize as `***`, their defaults are not shown either.
+		//
+		let mut sensitive = self.sensitive().to_vec();
+
+		sensitive.extend( secret_pointers( &serde_yaml::to_value( self.get() )? ) );
+
+		descend( &mut schema, defaults.as_ref(), "", &sensitive, partial );
+
+		Ok( schema )
+	}
+}
+
+
+
+// Set the default of a property and go on with its properties.
+//
+fn annotate( schema: &mut JsonValue, default: Option< &JsonValue >, ptr: &str, sensitive: &[ String ], partial: bool )
+{
+	// Mappings get the defaults on their properties instead.
+	//
+	if let ( Some( default ), JsonValue::Object( obj ) ) = ( default, &mut *schema )
+	{
+		if !default.is_object() && !is_sensitive( ptr, sensitive )
+		{
+			obj.insert( "default".to_string(), default.clone() );
+		}
+	}
+
+	descend( schema, default, ptr, sensitive, partial );
+}
+
+
+// Annotate the properties of a schema, and of the schemas it combines, eg. for an `Option`. Partial
+// schemas don't require any property.
+//
+fn descend( schema: &mut JsonValue, defaults: Option< &JsonValue >, ptr: &str, sensitive: &[ String ], partial: bool )
+{
+	let obj = match schema
+	{
+		JsonValue::Object( obj ) => obj,
+		_                        => return,
+	};
+
+	if partial
+	{
+		obj.remove( "required" );
+	}
+
+	if let Some( JsonValue::Object( properties ) ) = obj.get_mut( "properties" )
+	{
+		for ( key, property ) in properties.iter_mut()
+		{
+			let ptr = format!( "{}/{}", ptr, escape( key ) );
+
+			annotate( property, defaults.and_then( |d| d.get( key ) ), &ptr, sensitive, partial );
+		}
+	}
+
+	for key in &[ "allOf", "anyOf", "oneOf" ]
+	{
+		if let Some( JsonValue::Array( schemas ) ) = obj.get_mut( *key )
+		{
+			for schema in schemas
+			{
+				descend( schema, defaults, ptr, sensitive, partial );
+			}
+		}
+	}
+}
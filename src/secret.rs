@@ -0,0 +1,273 @@
+use crate :: { import::*, pointer::escape };
+use serde :: de::Error as _;
+
+
+/// What sensitive values are replaced with.
+//
+pub( crate ) const REDACTED: &str = "***";
+
+
+/// A wrapper for settings that should not end up in logs, like passwords and tokens. It deserializes
+/// like `T`, but `Debug` prints `***` and serializing it writes `***` instead of the value. Use
+/// [`Secret::expose`] to get at the value.
+///
+/// ```
+/// # use ekke_config::Secret;
+/// # #[ derive( serde::Deserialize, Debug ) ]
+/// struct DbOpts
+/// {
+///    user    : String        ,
+///    password: Secret<String>,
+/// }
+/// # let opts: DbOpts = serde_yaml::from_str( "{ user: admin, password: hunter2 }" ).unwrap();
+/// # assert_eq!( opts.password.expose(), "hunter2" );
+/// # assert!( !format!( "{:?}", opts ).contains( "hunter2" ) );
+/// ```
+///
+/// The layers of a [`Config`](crate::Config) hide the values of `Secret` fields as well. When the value does not
+/// deserialize, the error does not show it. To also keep them out of other error messages, mark their pointers as
+/// sensitive.
+///
+#[ derive( Clone, Copy, PartialEq, Eq, Hash, Default ) ]
+//
+pub struct Secret<T>( T );
+
+
+impl<T> Secret<T>
+{
+	/// Wrap a value.
+	///
+	pub fn new( value: T ) -> Self
+	{
+		Self( value )
+	}
+
+
+	/// Get a reference to the secret value.
+	///
+	pub fn expose( &self ) -> &T
+	{
+		&self.0
+	}
+
+
+	/// Unwrap the secret value.
+	///
+	pub fn into_inner( self ) -> T
+	{
+		self.0
+	}
+}
+
+
+impl<T> From<T> for Secret<T>
+{
+	fn from( value: T ) -> Self
+	{
+		Self( value )
+	}
+}
+
+
+impl<T> Debug for Secret<T>
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "Secret({})", REDACTED )
+	}
+}
+
+
+impl<T> Serialize for Secret<T>
+{
+	fn serialize<S: Serializer>( &self, serializer: S ) -> Result< S::Ok, S::Error >
+	{
+		serializer.serialize_str( REDACTED )
+	}
+}
+
+
+impl<'de, T> Deserialize<'de> for Secret<T> where T: Deserialize<'de>
+{
+	fn deserialize<D: Deserializer<'de>>( deserializer: D ) -> Result< Self, D::Error >
+	{
+		// The errors of T show the value, eg. `invalid type: string "hunter2", expected u16`.
+		//
+		T::deserialize( deserializer ).map( Self ).map_err( |_| D::Error::custom( "invalid value for a secret" ) )
+	}
+}
+
+
+
+// The schema of T, without a name of its own, so secrets are documented like the values they hold.
+//
+#[ cfg( feature = "schema" ) ]
+//
+impl<T> JsonSchema for Secret<T> where T: JsonSchema
+{
+	fn is_referenceable() -> bool
+	{
+		false
+	}
+
+	fn schema_name() -> String
+	{
+		T::schema_name()
+	}
+
+	fn json_schema( gen: &mut SchemaGenerator ) -> Schema
+	{
+		T::json_schema( gen )
+	}
+}
+
+
+
+// Whether the value at ptr is sensitive. A sensitive pointer covers everything below it.
+//
+pub( crate ) fn is_sensitive( ptr: &str, sensitive: &[ String ] ) -> bool
+{
+	sensitive.iter().any( |s| ptr == s || ( ptr.starts_with( s.as_str() ) && ptr[ s.len().. ].starts_with( '/' ) ) )
+}
+
+
+// The pointers of values that serialize as `***`, which is how Secret fields end up in serialized settings.
+//
+pub( crate ) fn secret_pointers( value: &Value ) -> Vec< String >
+{
+	let mut found = Vec::new();
+
+	collect_secrets( value, "", &mut found );
+
+	found
+}
+
+
+fn collect_secrets( value: &Value, ptr: &str, found: &mut Vec< String > )
+{
+	match value
+	{
+		Value::String( s ) if s == REDACTED => found.push( ptr.to_string() ),
+
+		Value::Mapping( map ) => for ( key, value ) in map
+		{
+			if let Value::String( k ) = key
+			{
+				collect_secrets( value, &format!( "{}/{}", ptr, escape( k ) ), found );
+			}
+		}
+
+		Value::Sequence( seq ) => for ( i, value ) in seq.iter().enumerate()
+		{
+			collect_secrets( value, &format!( "{}/{}", ptr, i ), found );
+		}
+
+		_ => {}
+	}
+}
+
+
+// Hide the sensitive values of settings that show up in message. Serde shows values as `string "x"`,
+// `integer `5``, `unknown variant `x`` and the like.
+//
+pub( crate ) fn redact_message( message: &str, settings: &Value, sensitive: &[ String ] ) -> String
+{
+	let mut found = Vec::new();
+
+	collect_sensitive( settings, "", sensitive, &mut found );
+
+	// Longer values first, so a value that contains another one is hidden as a whole.
+	//
+	found.sort_by_key( |s| std::cmp::Reverse( s.len() ) );
+
+	found.iter().fold( message.to_string(), |message, value|
+	{
+		message
+			.replace( &format!( "{:?}", value ), &format!( "{:?}", REDACTED ) )
+			.replace( &format!( "`{}`" , value ), &format!( "`{}`" , REDACTED ) )
+	})
+}
+
+
+// The scalars at or below sensitive pointers, as serde shows them.
+//
+fn collect_sensitive( value: &Value, ptr: &str, sensitive: &[ String ], found: &mut Vec< String > )
+{
+	match value
+	{
+		Value::Mapping( map ) => for ( key, value ) in map
+		{
+			if let Value::String( k ) = key
+			{
+				collect_sensitive( value, &format!( "{}/{}", ptr, escape( k ) ), sensitive, found );
+			}
+		}
+
+		Value::Sequence( seq ) => for ( i, value ) in seq.iter().enumerate()
+		{
+			collect_sensitive( value, &format!( "{}/{}", ptr, i ), sensitive, found );
+		}
+
+		_ if !is_sensitive( ptr, sensitive ) => {}
+
+		Value::String( s ) => found.push( s.clone()       ),
+		Value::Number( n ) => found.push( n.to_string()   ),
+		Value::Bool  ( b ) => found.push( b.to_string()   ),
+		Value::Null        => {}
+	}
+}
+
+
+// Whether the value at ptr is sensitive or has sensitive values below it.
+//
+#[ cfg( feature = "schema" ) ]
+//
+pub( crate ) fn covers_sensitive( ptr: &str, sensitive: &[ String ] ) -> bool
+{
+	is_sensitive( ptr, sensitive ) || sensitive.iter().any( |s| crate::pointer::within( s, ptr ) )
+}
+
+
+// Replace the values at sensitive pointers with `***`.
+//
+pub( crate ) fn redact( value: Value, sensitive: &[ String ] ) -> Value
+{
+	if sensitive.is_empty()
+	{
+		return value;
+	}
+
+	redact_at( value, "", sensitive )
+}
+
+
+fn redact_at( value: Value, ptr: &str, sensitive: &[ String ] ) -> Value
+{
+	if is_sensitive( ptr, sensitive )
+	{
+		return Value::String( REDACTED.to_string() );
+	}
+
+	match value
+	{
+		Value::Mapping( map ) => Value::Mapping( map.into_iter().map( |( key, value )|
+		{
+			let value = match &key
+			{
+				Value::String( k ) => redact_at( value, &format!( "{}/{}", ptr, escape( k ) ), sensitive ),
+				_                  => value,
+			};
+
+			( key, value )
+
+		}).collect() ),
+
+		Value::Sequence( seq ) => Value::Sequence( seq.into_iter().enumerate()
+
+			.map( |( i, value )| redact_at( value, &format!( "{}/{}", ptr, i ), sensitive ) )
+			.collect()
+		),
+
+		other => other,
+	}
+}
@@ -0,0 +1,261 @@
+use crate :: { import::*, Config, EkkeResult, Transaction };
+
+#[ cfg( feature = "stream" ) ]
+//
+use crate :: { ConfigStream, ConfigWatch };
+
+
+/// A thread safe handle to a [`Config`]. It can be cloned cheaply and sent to other threads.
+///
+/// Readers get an `Arc<T>` snapshot of the settings through [`SharedConfig::get`]. This never blocks,
+/// not even while a writer is merging in new configuration. Writers are serialized among themselves
+/// and publish a new snapshot atomically once the new settings have been generated successfully.
+///
+/// Every published snapshot increments a generation counter, so readers that hold on to a snapshot
+/// can cheaply check whether it's still current. [`SharedConfig::snapshot`] returns both together.
+/// With the `stream` feature, async code can also await new snapshots with `SharedConfig::updates`
+/// or `SharedConfig::watch`.
+///
+#[ derive( Debug ) ]
+//
+pub struct SharedConfig<T> where T: Clone + Serialize + Debug
+{
+	inner: Arc< Inner<T> >,
+}
+
+
+#[ derive( Debug ) ]
+//
+struct Inner<T> where T: Clone + Serialize + Debug
+{
+	config : Mutex  < Config<T>         >,
+
+	// The generation and the settings, swapped together so readers never see them mismatch.
+	//
+	current: ArcSwap< ( usize, Arc<T> ) >,
+
+	#[ cfg( feature = "stream" ) ]
+	//
+	subscribers: Mutex< Vec< UnboundedSender< Arc<T> > > >,
+
+	#[ cfg( feature = "stream" ) ]
+	//
+	watchers: Mutex< Vec< Weak< AtomicWaker > > >,
+}
+
+
+
+impl<T> SharedConfig<T> where T: Clone + DeserializeOwned + Serialize + Debug
+{
+	/// Wrap a config in a shared handle. The current settings become generation 0.
+	///
+	pub fn new( config: Config<T> ) -> Self
+	{
+		let current = ArcSwap::from_pointee(( 0, Arc::new( config.get().clone() ) ));
+
+		Self { inner: Arc::new( Inner
+		{
+			config: Mutex::new( config ),
+			current                    ,
+
+			#[ cfg( feature = "stream" ) ]
+			//
+			subscribers: Mutex::new( Vec::new() ),
+
+			#[ cfg( feature = "stream" ) ]
+			//
+			watchers: Mutex::new( Vec::new() ),
+		})}
+	}
+
+
+	/// Get a snapshot of the current settings. The snapshot is not affected by later changes,
+	/// call this method again to get the latest settings.
+	///
+	pub fn get( &self ) -> Arc<T>
+	{
+		self.inner.current.load().1.clone()
+	}
+
+
+	/// The generation of the current snapshot. It is incremented every time new settings are published.
+	///
+	pub fn generation( &self ) -> usize
+	{
+		self.inner.current.load().0
+	}
+
+
+	/// The generation and the settings of the current snapshot. Unlike separate calls to [`SharedConfig::generation`]
+	/// and [`SharedConfig::get`], these always belong together, even when a writer publishes in between.
+	///
+	pub fn snapshot( &self ) -> ( usize, Arc<T> )
+	{
+		let current = self.inner.current.load();
+
+		( current.0, current.1.clone() )
+	}
+
+
+	/// Merge userset settings and publish the new settings. See [`Config::merge_userset`].
+	///
+	pub fn merge_userset( &self, input: &str ) -> EkkeResult<()>
+	{
+		self.update( |cfg| cfg.merge_userset( input ) )
+	}
+
+
+	/// Merge runtime settings and publish the new settings. See [`Config::merge_runtime`].
+	///
+	pub fn merge_runtime( &self, input: &str ) -> EkkeResult<()>
+	{
+		self.update( |cfg| cfg.merge_runtime( input ) )
+	}
+
+
+	/// Re-read the configuration files and publish the new settings. See [`Config::reload`].
+	///
+	pub fn reload( &self ) -> EkkeResult<()>
+	{
+		self.update( |cfg| cfg.reload() )
+	}
+
+
+	/// Stage several changes in a [`Transaction`] and publish the result as a single new generation.
+	/// The transaction is committed when the closure returns successfully. When the closure or the commit
+	/// fails, nothing is published and the config is left untouched.
+	///
+	pub fn transaction<F, R>( &self, f: F ) -> EkkeResult<R>
+
+		where F: FnOnce( &mut Transaction<'_, T> ) -> EkkeResult<R>
+	{
+		self.update( |cfg|
+		{
+			let mut tx     = cfg.transaction();
+			let     result = f( &mut tx )?;
+
+			tx.commit()?;
+
+			Ok( result )
+		})
+	}
+
+
+	/// Run a closure with exclusive access to the underlying config. When it returns successfully,
+	/// the resulting settings are published as a new generation. When it returns an error, the
+	/// config is left untouched. Other writers are blocked during the closure, readers are not.
+	///
+	pub fn update<F, R>( &self, f: F ) -> EkkeResult<R>
+
+		where F: FnOnce( &mut Config<T> ) -> EkkeResult<R>
+	{
+		let mut config = self.inner.config.lock().unwrap_or_else( PoisonError::into_inner );
+
+		// Work on a copy so a failing closure can't leave a half updated config behind. The copy shares
+		// the states in the history with the original.
+		//
+		let mut next   = config.clone();
+		let     result = f( &mut next )?;
+
+		*config = next;
+
+		// Writers hold the lock, so nobody else publishes in between.
+		//
+		let generation = self.inner.current.load().0 + 1;
+		let snapshot   = Arc::new( config.get().clone() );
+
+		self.inner.current.store( Arc::new(( generation, snapshot.clone() )) );
+
+		#[ cfg( feature = "stream" ) ]
+		//
+		self.notify( snapshot );
+
+		Ok( result )
+	}
+
+
+	/// Run a closure with read access to the underlying config, eg. to inspect the individual layers.
+	/// This blocks writers for the duration of the closure.
+	///
+	pub fn read<F, R>( &self, f: F ) -> R
+
+		where F: FnOnce( &Config<T> ) -> R
+	{
+		f( &self.inner.config.lock().unwrap_or_else( PoisonError::into_inner ) )
+	}
+
+
+	/// A stream that yields every newly published snapshot. Snapshots published before this
+	/// call are not yielded.
+	///
+	#[ cfg( feature = "stream" ) ]
+	//
+	pub fn updates( &self ) -> ConfigStream<T>
+	{
+		ConfigStream::new( self.subscribe() )
+	}
+
+
+	/// A receiver that yields the latest snapshot whenever new settings have been published
+	/// since it was last polled.
+	///
+	#[ cfg( feature = "stream" ) ]
+	//
+	pub fn watch( &self ) -> ConfigWatch<T>
+	{
+		let waker = Arc::new( AtomicWaker::new() );
+
+		self.inner.watchers.lock().unwrap_or_else( PoisonError::into_inner ).push( Arc::downgrade( &waker ) );
+
+		ConfigWatch::new( self.clone(), waker )
+	}
+
+
+	#[ cfg( feature = "stream" ) ]
+	//
+	fn subscribe( &self ) -> UnboundedReceiver< Arc<T> >
+	{
+		let (tx, rx) = unbounded();
+
+		self.inner.subscribers.lock().unwrap_or_else( PoisonError::into_inner ).push( tx );
+
+		rx
+	}
+
+
+	// Send a snapshot to all subscribers and wake all watchers, forgetting about the ones that have gone away.
+	//
+	#[ cfg( feature = "stream" ) ]
+	//
+	fn notify( &self, snapshot: Arc<T> )
+	{
+		self.inner.subscribers.lock().unwrap_or_else( PoisonError::into_inner )
+
+			.retain( |tx| tx.unbounded_send( snapshot.clone() ).is_ok() );
+
+		self.inner.watchers.lock().unwrap_or_else( PoisonError::into_inner ).retain( |waker|
+		{
+			waker.upgrade().map( |waker| waker.wake() ).is_some()
+		});
+	}
+}
+
+
+
+impl<T> Clone for SharedConfig<T> where T: Clone + Serialize + Debug
+{
+	fn clone( &self ) -> Self
+	{
+		Self { inner: self.inner.clone() }
+	}
+}
+
+
+
+impl<T> From< Config<T> > for SharedConfig<T> where T: Clone + DeserializeOwned + Serialize + Debug
+{
+	fn from( config: Config<T> ) -> Self
+	{
+		Self::new( config )
+	}
+}
@@ -0,0 +1,550 @@
+use crate :: { import::*, Document, EkkeResult, EkkeCfgError, Layer, include::resolve_includes, lock::extract_locks, policy, interpolate::literal, strategy::{ extract_directives, Strategies }, tags::Resolvers, unset::extract_unsets };
+
+
+/// A provider of configuration data. Each source produces one [`Layer`] of a [`Config`](crate::Config).
+///
+/// ekke_config comes with sources for files, directories, strings, environment variables, command
+/// line options and in memory data. Implement this trait to load configuration from elsewhere, like
+/// a database, and add it with [`ConfigBuilder::source`](crate::ConfigBuilder::source).
+///
+pub trait Source: Debug + Send + Sync
+{
+	/// The name of the layer this source produces.
+	///
+	fn name( &self ) -> &str;
+
+	/// Where the data comes from, eg. a file path or an URI. Defaults to None.
+	///
+	fn origin( &self ) -> Option< String > { None }
+
+	/// Whether it's ok for this source not to be present. When a required source is missing,
+	/// building the config fails. Defaults to false.
+	///
+	fn optional( &self ) -> bool { false }
+
+	/// Whether loading this source again might give different data. If so, [`Config::reload`](crate::Config::reload)
+	/// will load it again. Defaults to false.
+	///
+	fn reloadable( &self ) -> bool { false }
+
+	/// Load the data. Return None if the source is not present, eg. the file does not exist.
+	///
+	fn load( &self ) -> EkkeResult< Option< Mapping > >;
+
+	/// Load the data together with its tags, like `!include` or `!locked`. Sources that read yaml text implement
+	/// this with [`Document::parse`](crate::Document::parse), so the tags in it work. Defaults to the data from
+	/// [`Source::load`], which has no tags.
+	///
+	fn load_document( &self ) -> EkkeResult< Option< Document > >
+	{
+		Ok( self.load()?.map( Document::from ) )
+	}
+
+	/// Load the data as several documents, like the files in a directory. A config merges them in order, with
+	/// the merge strategies and directives, like it merges layers. Defaults to the one document from
+	/// [`Source::load_document`].
+	///
+	fn load_documents( &self ) -> EkkeResult< Option< Vec< Document > > >
+	{
+		Ok( self.load_document()?.map( |doc| vec![ doc ] ) )
+	}
+}
+
+
+
+// The sources a config was created from. They are not configuration data, so they don't
+// affect equality.
+//
+#[ derive( Debug, Clone, Default ) ]
+//
+pub( crate ) struct Sources( pub( crate ) Vec< Arc< dyn Source > > );
+
+
+impl PartialEq for Sources
+{
+	fn eq( &self, _other: &Self ) -> bool { true }
+}
+
+impl Eq for Sources {}
+
+
+
+// Load a source into a layer. Returns None for optional sources that are not present.
+//
+// Includes are resolved relative to the origin of the source when it is a file or a directory.
+//
+pub( crate ) fn load_layer( source: &dyn Source, resolvers: &Resolvers, strategies: &Strategies ) -> EkkeResult< Option< Layer > >
+{
+	match source.load_documents()?
+	{
+		Some( docs ) =>
+		{
+			let origin    = source.origin().map( PathBuf::from ).filter( |path| path.exists() );
+			let mut layer: Option< Layer > = None;
+
+			for doc in docs
+			{
+				let next = make_layer( source.name(), doc, origin.as_deref(), resolvers )?;
+
+				match &mut layer
+				{
+					Some( layer ) => layer.merge( next, strategies ).context( format!( "Failed to merge the documents of layer: {}", source.name() ) )?,
+					None          => layer = Some( next ),
+				}
+			}
+
+			let layer = layer.unwrap_or_else( || Layer::new( source.name(), Mapping::new() ) );
+
+			Ok( Some( layer.with_origin( source.origin() ) ) )
+		}
+
+		None if source.optional() => Ok( None ),
+
+		None => Err( EkkeCfgError::MissingSource.context
+		(
+			format!( "layer: {}, origin: {}", source.name(), source.origin().unwrap_or_default() )
+
+		).into() ),
+	}
+}
+
+
+
+// Turn a document into a layer: resolve includes relative to base, take out the `!locked`, `!unset`
+// and merge directive tags and resolve the other tags.
+//
+pub( crate ) fn make_layer( name: &str, doc: Document, base: Option< &Path >, resolvers: &Resolvers ) -> EkkeResult< Layer >
+{
+	let ( data, tags ) = doc.into_parts();
+
+	let ( data, mut tags, includes ) = resolve_includes( data, tags, base )
+
+		.context( format!( "Failed to resolve includes for layer: {}", name ) )?
+	;
+
+	let locked          = extract_locks ( &mut tags       );
+	let ( data, unset ) = extract_unsets( data, &mut tags );
+
+	let directives = extract_directives( &data, &mut tags ).context( format!( "Invalid merge directive in layer: {}", name ) )?;
+
+	let data = resolvers.resolve( data, &tags ).context( format!( "Failed to resolve tags for layer: {}", name ) )?;
+
+	Ok( Layer::new( name, data ).with_includes( includes ).with_locked( locked ).with_unset( unset ).with_directives( directives ) )
+}
+
+
+
+/// A yaml file.
+///
+#[ derive( Debug, Clone ) ]
+//
+pub struct FileSource
+{
+	name    : String ,
+	path    : PathBuf,
+	optional: bool   ,
+}
+
+
+impl FileSource
+{
+	/// A file that must exist.
+	///
+	pub fn new( name: impl Into<String>, path: impl Into<PathBuf> ) -> Self
+	{
+		Self { name: name.into(), path: path.into(), optional: false }
+	}
+
+
+	/// A file that is skipped when it does not exist.
+	///
+	pub fn optional( name: impl Into<String>, path: impl Into<PathBuf> ) -> Self
+	{
+		Self { name: name.into(), path: path.into(), optional: true }
+	}
+}
+
+
+impl Source for FileSource
+{
+	fn name      ( &self ) -> &str             { &self.name                                       }
+	fn origin    ( &self ) -> Option< String > { Some( self.path.to_string_lossy().into_owned() ) }
+	fn optional  ( &self ) -> bool             { self.optional                                    }
+	fn reloadable( &self ) -> bool             { true                                             }
+
+	fn load( &self ) -> EkkeResult< Option< Mapping > >
+	{
+		self.load_document()?.map( Document::into_data ).transpose()
+	}
+
+	fn load_document( &self ) -> EkkeResult< Option< Document > >
+	{
+		if !self.path.exists()
+		{
+			return Ok( None );
+		}
+
+		Ok( Some( parse_file( &self.path )? ) )
+	}
+}
+
+
+
+/// A directory of yaml files, like /etc/my_app/conf.d. All files with a .yml or .yaml extension
+/// are merged in alphabetical order, with the merge strategies and directives of the config. Other
+/// files are ignored.
+///
+#[ derive( Debug, Clone ) ]
+//
+pub struct DirSource
+{
+	name    : String ,
+	path    : PathBuf,
+	optional: bool   ,
+}
+
+
+impl DirSource
+{
+	/// A directory that must exist.
+	///
+	pub fn new( name: impl Into<String>, path: impl Into<PathBuf> ) -> Self
+	{
+		Self { name: name.into(), path: path.into(), optional: false }
+	}
+
+
+	/// A directory that is skipped when it does not exist.
+	///
+	pub fn optional( name: impl Into<String>, path: impl Into<PathBuf> ) -> Self
+	{
+		Self { name: name.into(), path: path.into(), optional: true }
+	}
+}
+
+
+impl Source for DirSource
+{
+	fn name      ( &self ) -> &str             { &self.name                                       }
+	fn origin    ( &self ) -> Option< String > { Some( self.path.to_string_lossy().into_owned() ) }
+	fn optional  ( &self ) -> bool             { self.optional                                    }
+	fn reloadable( &self ) -> bool             { true                                             }
+
+	fn load( &self ) -> EkkeResult< Option< Mapping > >
+	{
+		self.load_document()?.map( Document::into_data ).transpose()
+	}
+
+	// Without the strategies of a config, the files are merged as usual.
+	//
+	fn load_document( &self ) -> EkkeResult< Option< Document > >
+	{
+		let docs = match self.load_documents()?
+		{
+			Some( docs ) => docs,
+			None         => return Ok( None ),
+		};
+
+		let mut doc = Document::from( Mapping::new() );
+
+		for next in docs
+		{
+			doc.merge( next )?;
+		}
+
+		Ok( Some( doc ) )
+	}
+
+	fn load_documents( &self ) -> EkkeResult< Option< Vec< Document > > >
+	{
+		if !self.path.exists()
+		{
+			return Ok( None );
+		}
+
+		let mut files = Vec::new();
+
+		for entry in fs::read_dir( &self.path ).context( format!( "{:?}", self.path ) )?
+		{
+			let path = entry?.path();
+
+			match path.extension().and_then( |ext| ext.to_str() )
+			{
+				Some( "yml" ) | Some( "yaml" ) if path.is_file() => files.push( path ),
+				_                                                 => {}
+			}
+		}
+
+		files.sort();
+
+		files.iter().map( |file| parse_file( file ) ).collect::< EkkeResult<_> >().map( Some )
+	}
+}
+
+
+
+/// A yaml string.
+///
+#[ derive( Debug, Clone ) ]
+//
+pub struct StrSource
+{
+	name : String,
+	input: String,
+}
+
+
+impl StrSource
+{
+	/// Create a source from a yaml string.
+	///
+	pub fn new( name: impl Into<String>, input: impl Into<String> ) -> Self
+	{
+		Self { name: name.into(), input: input.into() }
+	}
+}
+
+
+impl Source for StrSource
+{
+	fn name( &self ) -> &str { &self.name }
+
+	fn load( &self ) -> EkkeResult< Option< Mapping > >
+	{
+		self.load_document()?.map( Document::into_data ).transpose()
+	}
+
+	fn load_document( &self ) -> EkkeResult< Option< Document > >
+	{
+		Ok( Some( Document::parse( &self.input ).context( format!( "Failed to parse yaml for layer: {}", self.name ) )? ) )
+	}
+}
+
+
+
+/// Environment variables starting with a prefix. The rest of the variable name is lowercased
+/// and split on double underscores to find the key, so with prefix `MY_APP_`, the variable
+/// `MY_APP_OTHER_COMP__ALGO` sets `/other_comp/algo`. Values are parsed as yaml, so numbers
+/// and lists work. Anything that's not valid yaml is taken as a string, and so is an empty value.
+/// Values are not interpolated. Variables with a name that isn't unicode are ignored, but a value
+/// that isn't unicode is an error.
+///
+#[ derive( Debug, Clone ) ]
+//
+pub struct EnvSource
+{
+	name  : String,
+	prefix: String,
+}
+
+
+impl EnvSource
+{
+	/// Create a source from the environment variables starting with `prefix`.
+	///
+	pub fn new( name: impl Into<String>, prefix: impl Into<String> ) -> Self
+	{
+		Self { name: name.into(), prefix: prefix.into() }
+	}
+}
+
+
+impl Source for EnvSource
+{
+	fn name      ( &self ) -> &str             { &self.name                               }
+	fn origin    ( &self ) -> Option< String > { Some( format!( "env:{}", self.prefix ) ) }
+	fn reloadable( &self ) -> bool             { true                                     }
+
+	fn load( &self ) -> EkkeResult< Option< Mapping > >
+	{
+		let mut data = Mapping::new();
+
+		for ( var, value ) in env::vars_os()
+		{
+			// Names that aren't unicode can't be keys.
+			//
+			let var = match var.to_str()
+			{
+				Some( var ) => var,
+				None        => continue,
+			};
+
+			if !var.starts_with( &self.prefix ) || var.len() == self.prefix.len()
+			{
+				continue;
+			}
+
+			let value = value.into_string()
+
+				.map_err( |_| EkkeCfgError::ConfigParse.context( format!( "Environment variable {} is not valid unicode", var ) ) )?
+			;
+
+			let keys: Vec<String> = var[ self.prefix.len().. ].split( "__" ).map( str::to_lowercase ).collect();
+
+			data.merge( nest( &keys, scalar( &value ) ) )?;
+		}
+
+		Ok( Some( data ) )
+	}
+}
+
+
+
+/// Command line options in the form `other_comp.algo=euler`. The key is split on dots and values are
+/// parsed like for [`EnvSource`]. Extracting these from the actual command line is left to the argument
+/// parser of the application.
+///
+#[ derive( Debug, Clone ) ]
+//
+pub struct CliSource
+{
+	name: String       ,
+	args: Vec< String >,
+}
+
+
+impl CliSource
+{
+	/// Create a source from a list of `key=value` options.
+	///
+	pub fn new<I, S>( name: impl Into<String>, args: I ) -> Self
+
+		where I: IntoIterator<Item=S>, S: Into<String>
+	{
+		Self { name: name.into(), args: args.into_iter().map( Into::into ).collect() }
+	}
+}
+
+
+impl Source for CliSource
+{
+	fn name  ( &self ) -> &str             { &self.name                         }
+	fn origin( &self ) -> Option< String > { Some( "command line".to_string() ) }
+
+	fn load( &self ) -> EkkeResult< Option< Mapping > >
+	{
+		let mut data = Mapping::new();
+
+		for arg in &self.args
+		{
+			let mut split = arg.splitn( 2, '=' );
+
+			let key   = split.next().unwrap_or_default();
+			let value = split.next()
+
+				.ok_or_else( || EkkeCfgError::ConfigParse.context( format!( "Command line option must be of the form key=value, got: {}", arg ) ) )?
+			;
+
+			let keys: Vec<String> = key.split( '.' ).map( String::from ).collect();
+
+			data.merge( nest( &keys, scalar( value ) ) )?;
+		}
+
+		Ok( Some( data ) )
+	}
+}
+
+
+
+/// Data that is already in memory.
+///
+#[ derive( Debug, Clone ) ]
+//
+pub struct ValueSource
+{
+	name: String ,
+	data: Mapping,
+}
+
+
+impl ValueSource
+{
+	/// Create a source from a mapping.
+	///
+	pub fn new( name: impl Into<String>, data: Mapping ) -> Self
+	{
+		Self { name: name.into(), data }
+	}
+}
+
+
+impl Source for ValueSource
+{
+	fn name( &self ) -> &str { &self.name }
+
+	fn load( &self ) -> EkkeResult< Option< Mapping > >
+	{
+		Ok( Some( self.data.clone() ) )
+	}
+}
+
+
+
+// Helper methods
+//
+// All files are read through here, so the active file policy sees them.
+//
+pub( crate ) fn read_file( path: &Path ) -> EkkeResult< String >
+{
+	let     file       = policy::open( path )?;
+	let mut buf_reader = BufReader::new( file );
+	let mut contents   = String::new();
+
+	buf_reader.read_to_string( &mut contents )?;
+
+	Ok( contents )
+}
+
+
+pub( crate ) fn parse_file( path: &Path ) -> EkkeResult< Document >
+{
+	let data =
+
+		Document::parse( &read_file( path ).context( format!( "{:?}", path ) )? )
+
+		.context( format!( "Failed to parse yaml at: {:?}", path ) )?
+	;
+
+	Ok( data )
+}
+
+
+// Interpret a string from the environment or command line as yaml, so numbers and lists work.
+// Anything that isn't valid yaml is taken as a plain string. Only an explicit null, like `~`,
+// unsets a value. Empty values, and ones that are only a comment, are strings as well. The result
+// is taken literally, so a `${` in the input is not interpolated.
+//
+fn scalar( input: &str ) -> Value
+{
+	let value = match from_str( input )
+	{
+		Ok( Value::Null ) if !matches!( input.trim(), "~" | "null" | "Null" | "NULL" ) => Value::String( input.to_string() ),
+
+		Ok ( value ) => value,
+		Err( _     ) => Value::String( input.to_string() ),
+	};
+
+	literal( value )
+}
+
+
+// Turn a list of keys into nested mappings with value at the bottom.
+//
+pub( crate ) fn nest( keys: &[String], value: Value ) -> Mapping
+{
+	let ( last, parents ) = match keys.split_last()
+	{
+		Some( split ) => split               ,
+		None          => return Mapping::new(),
+	};
+
+	let mut map = Mapping::new();
+	map.insert( last.as_str().into(), value );
+
+	parents.iter().rev().fold( map, |map, key|
+	{
+		let mut parent = Mapping::new();
+		parent.insert( key.as_str().into(), Value::Mapping( map ) );
+		parent
+	})
+}
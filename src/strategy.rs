@@ -0,0 +1,280 @@
+use crate :: { import::*, EkkeResult, EkkeCfgError, Pointer, pointer::{ jptr_mut, remove_at, unescape, within }, yaml::Tags };
+
+
+/// How to merge a sequence with the sequence at the same place in the layers below. Without a strategy,
+/// sequences are replaced and mappings are merged recursively.
+///
+/// Declare strategies for json pointers under a `merge` meta key in the defaults file:
+///
+/// ```yaml
+/// merge:
+///   /other_comp/primes: append
+///   /my_app/plugins   : { by_key: name }
+///
+/// default:
+///   ...
+/// ```
+///
+/// or with [`ConfigBuilder::merge_strategy`](crate::ConfigBuilder::merge_strategy) and
+/// [`Config::set_merge_strategy`](crate::Config::set_merge_strategy).
+///
+/// A layer can also choose the strategy for one of its values with a tag: `!replace`, `!append`, `!prepend`
+/// or `!union`, eg. `primes: !append [ 11 ]`. This overrides the declared strategy for that layer. Using one
+/// of these on a value that is not a sequence, or when the layers below have something other than a sequence
+/// there, is an error. `!replace` works on any value, so `other_comp: !replace { algo: euler }` throws away
+/// everything the layers below set in `other_comp`.
+///
+#[ derive( Debug, Clone, PartialEq, Eq, Deserialize ) ]
+#[ serde( rename_all = "snake_case" ) ]
+//
+pub enum MergeStrategy
+{
+	/// The new sequence replaces the old one. On a mapping, the new mapping replaces the old one instead of
+	/// being merged into it.
+	///
+	Replace,
+
+	/// The new elements go after the old ones.
+	///
+	Append,
+
+	/// The new elements go before the old ones.
+	///
+	Prepend,
+
+	/// The new elements that are not in the old sequence yet go after the old ones.
+	///
+	Union,
+
+	/// For sequences of mappings: a new mapping that has the same value for the given key as an old one
+	/// is merged into it. The other new elements go after the old ones.
+	///
+	ByKey( String ),
+}
+
+
+impl MergeStrategy
+{
+	// Combine old and new sequences according to this strategy.
+	//
+	fn combine( &self, old: &[ Value ], new: Vec< Value > ) -> MergeResult< Vec< Value > >
+	{
+		Ok( match self
+		{
+			Self::Replace => new,
+			Self::Append  => old.iter().cloned().chain( new ).collect(),
+			Self::Prepend => new.into_iter().chain( old.iter().cloned() ).collect(),
+
+			Self::Union =>
+			{
+				let mut out = old.to_vec();
+
+				for value in new
+				{
+					if !out.contains( &value ) { out.push( value ); }
+				}
+
+				out
+			}
+
+			Self::ByKey( key ) =>
+			{
+				let mut out = old.to_vec();
+				let     key = Value::from( key.as_str() );
+
+				for value in new
+				{
+					let id    = value.as_mapping().and_then( |map| map.get( &key ) ).cloned();
+					let found = id.and_then( |id| out.iter().position( |old| old.as_mapping().and_then( |map| map.get( &key ) ) == Some( &id ) ) );
+
+					match ( found.map( |index| &mut out[ index ] ), value )
+					{
+						( Some( Value::Mapping( old ) ), Value::Mapping( value ) ) => old.merge( value )?,
+						( _                            , value                   ) => out.push( value ) ,
+					}
+				}
+
+				out
+			}
+		})
+	}
+}
+
+
+
+// The merge strategies of a config or a layer, by json pointer.
+//
+pub( crate ) type Strategies = BTreeMap< String, MergeStrategy >;
+
+
+// The tags that choose a strategy for a single value.
+//
+const DIRECTIVES: [( &str, MergeStrategy ); 4] =
+[
+	( "!replace", MergeStrategy::Replace ),
+	( "!append" , MergeStrategy::Append  ),
+	( "!prepend", MergeStrategy::Prepend ),
+	( "!union"  , MergeStrategy::Union   ),
+];
+
+
+// Whether tag is one of the merge directives.
+//
+pub( crate ) fn is_directive( tag: &str ) -> bool
+{
+	DIRECTIVES.iter().any( |( t, _ )| *t == tag )
+}
+
+
+// Take the merge directives out of the tags. Returns the strategy for each tagged pointer.
+//
+pub( crate ) fn extract_directives( root: &Value, tags: &mut Tags ) -> EkkeResult< Strategies >
+{
+	let mut directives = Strategies::new();
+
+	for ( tag, strategy ) in &DIRECTIVES
+	{
+		directives.extend( tags.take( tag ).into_iter().map( |ptr| ( ptr, strategy.clone() ) ) );
+	}
+
+	for ( ptr, strategy ) in &directives
+	{
+		match ( strategy, root.jptr( ptr ) )
+		{
+			( MergeStrategy::Replace, _                       ) => {}
+			( _                     , Some( Value::Sequence(_) ) ) => {}
+
+			_ => return Err( EkkeCfgError::ConfigParse.context( format!( "{} at {} takes a sequence", directive( strategy ), ptr ) ).into() ),
+		}
+	}
+
+	Ok( directives )
+}
+
+
+fn directive( strategy: &MergeStrategy ) -> &'static str
+{
+	DIRECTIVES.iter().find( |( _, s )| s == strategy ).map( |( tag, _ )| *tag ).unwrap_or( "merge strategy" )
+}
+
+
+// Merge data into base, combining the values at the pointers that have a strategy. Everything else
+// is merged as usual. The directives of the layer data comes from override the declared strategies.
+// Unlike those, they fail when the value in base does not fit. Replacing a value keeps the locked
+// values below it.
+//
+pub( crate ) fn merge_with( base: &mut Mapping, data: Mapping, strategies: &Strategies, directives: &Strategies, locked: &[ String ] ) -> MergeResult<()>
+{
+	let mut data = Value::Mapping( data );
+	let mut old  = Value::Mapping( std::mem::take( base ) );
+
+	let declared = strategies.iter().filter( |( ptr, _ )| !directives.contains_key( *ptr ) ).map( |( p, s )| ( p, s, false ) );
+	let inline   = directives.iter().map( |( p, s )| ( p, s, true ) );
+
+	let result = declared.chain( inline ).try_for_each( |( ptr, strategy, strict )| combine_at( &mut old, &mut data, ptr, strategy, strict, locked ) );
+
+	// Put base back, also when combining failed.
+	//
+	if let Value::Mapping( old ) = old { *base = old; }
+
+	result?;
+
+	match data
+	{
+		Value::Mapping( data ) => base.merge( data ),
+		_                      => Ok(()),
+	}
+}
+
+
+// Prepare the value at ptr in data to be merged into old according to strategy.
+//
+fn combine_at( old: &mut Value, data: &mut Value, ptr: &str, strategy: &MergeStrategy, strict: bool, locked: &[ String ] ) -> MergeResult<()>
+{
+	let new = match jptr_mut( data, ptr )
+	{
+		Some( new ) => new,
+		None        => return Ok(()),
+	};
+
+	// Without the old value, there is nothing to merge with.
+	//
+	if *strategy == MergeStrategy::Replace
+	{
+		replace_at( old, ptr, locked );
+		return Ok(());
+	}
+
+	match ( old.jptr( ptr ), new )
+	{
+		( Some( Value::Sequence( old ) ), Value::Sequence( new ) ) =>
+		{
+			let combined = strategy.combine( old, std::mem::take( new ) )?;
+
+			*new = combined;
+		}
+
+		( None, _ ) | ( Some( Value::Null ), _ ) => {}
+
+		( Some(_), _ ) if strict => return Err( EkkeCfgError::ConfigParse.context
+		(
+			format!( "{} at {} can only be merged with a sequence, but the layers below have something else there", directive( strategy ), ptr )
+
+		).into() ),
+
+		_ => {}
+	}
+
+	Ok(())
+}
+
+
+// Remove the value at ptr from old, except for the locked values below it.
+//
+fn replace_at( old: &mut Value, ptr: &str, locked: &[ String ] )
+{
+	let kept: Vec<( &String, Value )> = locked.iter()
+
+		.filter    ( |l| within( l, ptr ) )
+		.filter_map( |l| Some(( l, old.jptr( l )?.clone() )) )
+		.collect()
+	;
+
+	remove_at( old, ptr );
+
+	for ( ptr, value ) in kept
+	{
+		put_at( old, ptr, value );
+	}
+}
+
+
+// Set the value at ptr, creating the mappings above it as needed.
+//
+fn put_at( root: &mut Value, ptr: &str, value: Value )
+{
+	let mut node = root;
+
+	for token in ptr.split( '/' ).skip( 1 )
+	{
+		let key: Value = unescape( token ).into();
+
+		node = match node
+		{
+			Value::Mapping( map ) =>
+			{
+				if !map.contains_key( &key ) { map.insert( key.clone(), Value::Mapping( Mapping::new() ) ); }
+
+				match map.get_mut( &key )
+				{
+					Some( next ) => next,
+					None         => return,
+				}
+			}
+
+			_ => return,
+		};
+	}
+
+	*node = value;
+}
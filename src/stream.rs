@@ -0,0 +1,107 @@
+//! Async notification of configuration updates. Only available with the `stream` feature.
+//
+use crate :: { import::*, SharedConfig };
+
+
+/// A stream of settings snapshots published by a [`SharedConfig`]. Every published generation is
+/// yielded, in order. Obtained from [`SharedConfig::updates`].
+///
+/// The stream ends when all handles to the SharedConfig have been dropped.
+///
+#[ derive( Debug ) ]
+//
+pub struct ConfigStream<T>
+{
+	rx: UnboundedReceiver< Arc<T> >,
+}
+
+
+impl<T> ConfigStream<T>
+{
+	pub(crate) fn new( rx: UnboundedReceiver< Arc<T> > ) -> Self
+	{
+		Self { rx }
+	}
+}
+
+
+impl<T> Stream for ConfigStream<T>
+{
+	type Item = Arc<T>;
+
+	fn poll_next( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< Option<Self::Item> >
+	{
+		Pin::new( &mut self.rx ).poll_next( cx )
+	}
+}
+
+
+
+/// A receiver that always sees the latest settings. Contrary to [`ConfigStream`], when several
+/// generations are published before the receiver is polled, only the last one is yielded.
+/// Obtained from [`SharedConfig::watch`].
+///
+/// Since the receiver holds a handle to the SharedConfig, it never ends.
+///
+#[ derive( Debug ) ]
+//
+pub struct ConfigWatch<T> where T: Clone + Serialize + Debug
+{
+	shared: SharedConfig<T>  ,
+	seen  : usize            ,
+	waker : Arc< AtomicWaker >,
+}
+
+
+impl<T> ConfigWatch<T> where T: Clone + DeserializeOwned + Serialize + Debug
+{
+	pub(crate) fn new( shared: SharedConfig<T>, waker: Arc< AtomicWaker > ) -> Self
+	{
+		let seen = shared.generation();
+
+		Self { shared, seen, waker }
+	}
+
+
+	/// The latest settings, whether or not they have been seen already.
+	///
+	pub fn borrow( &self ) -> Arc<T>
+	{
+		self.shared.get()
+	}
+
+
+	/// Wait until settings are published that this receiver hasn't seen yet and return them.
+	///
+	pub async fn changed( &mut self ) -> Arc<T>
+	{
+		// Never returns None, since we hold a handle to the SharedConfig.
+		//
+		self.next().await.expect( "ConfigWatch holds a handle to the SharedConfig" )
+	}
+}
+
+
+impl<T> Stream for ConfigWatch<T> where T: Clone + DeserializeOwned + Serialize + Debug
+{
+	type Item = Arc<T>;
+
+	fn poll_next( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll< Option<Self::Item> >
+	{
+		// Register before checking the generation, so a publish in between still wakes us. Publishing
+		// only wakes the task, however many generations go by before it runs.
+		//
+		self.waker.register( cx.waker() );
+
+		let ( generation, settings ) = self.shared.snapshot();
+
+		if generation == self.seen
+		{
+			return Poll::Pending;
+		}
+
+		self.seen = generation;
+
+		Poll::Ready( Some( settings ) )
+	}
+}
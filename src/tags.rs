@@ -0,0 +1,221 @@
+use crate :: { import::*, EkkeResult, EkkeCfgError, EncryptedTag, interpolate::literal, pointer::{ jptr_mut, within }, source::read_file, yaml::{ self, Tags } };
+
+
+/// Resolves values with a custom yaml tag, like `token: !file /run/secrets/token`. Tagged values are
+/// resolved when a layer is loaded, before layers are merged.
+///
+/// [`EnvTag`], [`FileTag`] and [`EncryptedTag`](crate::EncryptedTag) are enabled by default. [`CmdTag`] runs commands,
+/// so it has to be enabled explicitly with [`ConfigBuilder::resolver`](crate::ConfigBuilder::resolver). Applications can
+/// add their own resolvers the same way. A resolver replaces an earlier one for the same tag.
+///
+pub trait TagResolver: Debug + Send + Sync
+{
+	/// The tag this resolver handles, including the `!`, eg. `!env`.
+	///
+	fn tag( &self ) -> &str;
+
+	/// Compute the value for a tagged node. `value` is the node without the tag.
+	///
+	fn resolve( &self, value: &Value ) -> EkkeResult< Value >;
+}
+
+
+
+/// `!env VAR` is replaced by the environment variable VAR. The value is interpreted like a plain yaml scalar,
+/// so numbers and booleans work. It's an error if the variable is not set.
+///
+#[ derive( Debug, Clone, Copy, Default ) ]
+//
+pub struct EnvTag;
+
+
+impl TagResolver for EnvTag
+{
+	fn tag( &self ) -> &str { "!env" }
+
+	fn resolve( &self, value: &Value ) -> EkkeResult< Value >
+	{
+		let var = string( self.tag(), value )?;
+
+		let value = env::var( var ).map_err( |_| EkkeCfgError::UnsetVariable( var.to_string() ) )?;
+
+		yaml::infer( &value )
+	}
+}
+
+
+
+/// `!file path` is replaced by the content of the file at path, as a string. A trailing newline is removed.
+/// Relative paths are relative to the current working directory. Meant for secrets, like tokens
+/// mounted in `/run/secrets`.
+///
+#[ derive( Debug, Clone, Copy, Default ) ]
+//
+pub struct FileTag;
+
+
+impl TagResolver for FileTag
+{
+	fn tag( &self ) -> &str { "!file" }
+
+	fn resolve( &self, value: &Value ) -> EkkeResult< Value >
+	{
+		let path     = string( self.tag(), value )?;
+		let contents = read_file( Path::new( path ) ).context( format!( "{:?}", path ) )?;
+
+		Ok( Value::String( trim_newline( contents ) ) )
+	}
+}
+
+
+
+/// `!cmd command` is replaced by the standard output of a command, with a trailing newline removed. A string is
+/// run by `sh -c`, a list is taken as the program and its arguments. The output is interpreted like a plain yaml scalar.
+/// It's an error if the command fails.
+///
+/// Not enabled by default, since it allows configuration files to run programs.
+///
+#[ derive( Debug, Clone, Copy, Default ) ]
+//
+pub struct CmdTag;
+
+
+impl TagResolver for CmdTag
+{
+	fn tag( &self ) -> &str { "!cmd" }
+
+	fn resolve( &self, value: &Value ) -> EkkeResult< Value >
+	{
+		let mut cmd = match value
+		{
+			Value::String( line ) =>
+			{
+				let mut cmd = Command::new( "sh" );
+				cmd.arg( "-c" ).arg( line );
+				cmd
+			}
+
+			Value::Sequence( args ) if !args.is_empty() =>
+			{
+				let args: Vec<&str> = args.iter().map( |arg| string( self.tag(), arg ) ).collect::< EkkeResult<_> >()?;
+
+				let mut cmd = Command::new( args[0] );
+				cmd.args( &args[ 1.. ] );
+				cmd
+			}
+
+			_ => return Err( EkkeCfgError::ConfigParse.context( "!cmd takes a string or a non empty list of strings" ).into() ),
+		};
+
+		let output = cmd.output().context( format!( "Failed to run: {:?}", cmd ) )?;
+
+		if !output.status.success()
+		{
+			return Err( EkkeCfgError::ConfigParse.context
+			(
+				format!( "Command {:?} failed with {}: {}", cmd, output.status, String::from_utf8_lossy( &output.stderr ).trim() )
+
+			).into() );
+		}
+
+		let stdout = String::from_utf8( output.stdout ).context( format!( "Output of {:?} is not valid utf8", cmd ) )?;
+
+		yaml::infer( &trim_newline( stdout ) )
+	}
+}
+
+
+
+// The tag resolvers of a config. They are not configuration data, so they don't affect equality.
+//
+#[ derive( Debug, Clone ) ]
+//
+pub( crate ) struct Resolvers( Vec< Arc< dyn TagResolver > > );
+
+
+impl Default for Resolvers
+{
+	fn default() -> Self
+	{
+		Self( vec![ Arc::new( EnvTag ), Arc::new( FileTag ), Arc::new( EncryptedTag::default() ) ] )
+	}
+}
+
+
+impl PartialEq for Resolvers
+{
+	fn eq( &self, _other: &Self ) -> bool { true }
+}
+
+impl Eq for Resolvers {}
+
+
+impl Resolvers
+{
+	pub( crate ) fn add( &mut self, resolver: Arc< dyn TagResolver > )
+	{
+		self.0.push( resolver );
+	}
+
+
+	// Resolve the tagged values in data that have a resolver. The tags below a resolved value are
+	// dropped, since the resolver replaces the value. What a resolver returns is taken literally, so
+	// a secret holding `${` is not interpolated.
+	//
+	pub( crate ) fn resolve( &self, mut data: Value, tags: &Tags ) -> EkkeResult< Mapping >
+	{
+		let mut resolved = Vec::< &str >::new();
+
+		for ( ptr, tag ) in tags.iter()
+		{
+			if resolved.iter().any( |r| within( ptr, r ) ) { continue }
+
+			let resolver = match self.0.iter().rev().find( |r| r.tag() == tag )
+			{
+				Some( resolver ) => resolver,
+
+				None if tag == CmdTag.tag() => return Err( EkkeCfgError::ConfigParse.context
+				(
+					format!( "!cmd at {} is not enabled, add the CmdTag resolver to allow running commands", ptr )
+
+				).into() ),
+
+				None => continue,
+			};
+
+			if let Some( value ) = jptr_mut( &mut data, ptr )
+			{
+				*value = literal( resolver.resolve( value ).context( format!( "Failed to resolve {} at: {}", tag, ptr ) )? );
+
+				resolved.push( ptr );
+			}
+		}
+
+		match data
+		{
+			Value::Mapping( data ) => Ok( data ),
+
+			_ => Err( EkkeCfgError::ConfigParse.context( "A tag in the root of a configuration must produce a mapping." ).into() ),
+		}
+	}
+}
+
+
+
+fn string<'a>( tag: &str, value: &'a Value ) -> EkkeResult< &'a str >
+{
+	match value
+	{
+		Value::String( s ) => Ok( s ),
+		_                  => Err( EkkeCfgError::ConfigParse.context( format!( "{} takes a string", tag ) ).into() ),
+	}
+}
+
+
+fn trim_newline( mut s: String ) -> String
+{
+	if s.ends_with( '\n' ) { s.pop(); }
+	if s.ends_with( '\r' ) { s.pop(); }
+
+	s
+}
@@ -0,0 +1,166 @@
+use crate :: { import::*, Config, EkkeResult, Layer, config::{ nest_at, to_mapping } };
+
+
+/// A batch of changes to the layers of a [`Config`]. The changes are staged without regenerating the settings,
+/// so combinations that are only valid together can be made one at a time. [`Transaction::commit`] generates
+/// the settings once. If that fails, or the transaction is dropped without committing, all changes are discarded.
+///
+/// ```
+/// # use ekke_config::Config;
+/// # use std::convert::TryFrom;
+/// # #[ derive( serde::Serialize, serde::Deserialize, Debug, Clone ) ] struct Settings { min: u32, max: u32 }
+/// #
+/// let mut config: Config<Settings> = Config::try_from( "default: { min: 1, max: 10 }" )?;
+///
+/// let mut tx = config.transaction();
+///
+/// tx.merge_runtime( "min: 20" )?;
+/// tx.merge_runtime( "max: 30" )?;
+/// tx.commit()?;
+///
+/// assert_eq!( config.get().min, 20 );
+/// # Ok::<(), failure::Error>(())
+/// ```
+///
+/// Every change is checked when it is staged, eg. for locked values or invalid patches. A change that fails is undone
+/// on its own, and the transaction can go on.
+///
+#[ derive( Debug ) ]
+//
+pub struct Transaction<'a, T> where T: Clone + DeserializeOwned + Serialize + Debug
+{
+	config: &'a mut Config<T>        ,
+	backup: Option< Vec< Layer > >   ,
+}
+
+
+
+impl<'a, T> Transaction<'a, T> where T: Clone + DeserializeOwned + Serialize + Debug
+{
+	pub( crate ) fn new( config: &'a mut Config<T> ) -> Self
+	{
+		let backup = Some( config.layers().to_vec() );
+
+		Self { config, backup }
+	}
+
+
+	/// Stage merging userset settings. See [`Config::merge_userset`].
+	///
+	pub fn merge_userset( &mut self, input: &str ) -> EkkeResult<()>
+	{
+		self.merge_layer( Layer::USERSET, input )
+	}
+
+
+	/// Stage merging runtime settings. See [`Config::merge_runtime`].
+	///
+	pub fn merge_runtime( &mut self, input: &str ) -> EkkeResult<()>
+	{
+		self.merge_layer( Layer::RUNTIME, input )
+	}
+
+
+	/// Stage merging settings into a layer. See [`Config::merge_layer`].
+	///
+	pub fn merge_layer( &mut self, name: &str, input: &str ) -> EkkeResult<()>
+	{
+		self.stage( |cfg| cfg.stage_merge( name, input ) )
+	}
+
+
+	/// Stage merging a value into a layer. See [`Config::merge_layer_value`].
+	///
+	pub fn merge_layer_value( &mut self, name: &str, value: impl Serialize ) -> EkkeResult<()>
+	{
+		let data = to_mapping( value )?;
+
+		self.stage( |cfg| cfg.stage_merge_data( name, data.into() ) )
+	}
+
+
+	/// Stage merging a value at a json pointer into a layer. See [`Config::merge_layer_at`].
+	///
+	pub fn merge_layer_at( &mut self, name: &str, ptr: &str, value: impl Serialize ) -> EkkeResult<()>
+	{
+		let data = nest_at( ptr, value )?;
+
+		self.stage( |cfg| cfg.stage_merge_data( name, data.into() ) )
+	}
+
+
+	/// Stage a json patch of a layer. See [`Config::patch_layer`].
+	///
+	pub fn patch_layer( &mut self, name: &str, patch: &str ) -> EkkeResult<()>
+	{
+		self.stage( |cfg| cfg.stage_patch( name, patch ) )
+	}
+
+
+	/// Stage a json merge patch of a layer. See [`Config::merge_patch_layer`].
+	///
+	pub fn merge_patch_layer( &mut self, name: &str, patch: &str ) -> EkkeResult<()>
+	{
+		self.stage( |cfg| cfg.stage_merge_patch( name, patch ) )
+	}
+
+
+	/// The layers with the changes staged so far.
+	///
+	pub fn layers( &self ) -> &[ Layer ]
+	{
+		self.config.layers()
+	}
+
+
+	/// Generate the settings from the staged layers. When that fails, the config is restored to what it
+	/// was before the transaction and the error is returned.
+	///
+	pub fn commit( mut self ) -> EkkeResult<()>
+	{
+		self.config.regen()?;
+
+		// Nothing to roll back anymore. Undoing the transaction goes back to where it started.
+		//
+		if let Some( backup ) = self.backup.take()
+		{
+			self.config.record( &backup );
+		}
+
+		Ok(())
+	}
+
+
+	/// Discard all staged changes. Dropping the transaction does the same.
+	///
+	pub fn rollback( self ) {}
+
+
+	// Run a change, undoing it if it fails.
+	//
+	fn stage( &mut self, f: impl FnOnce( &mut Config<T> ) -> EkkeResult<()> ) -> EkkeResult<()>
+	{
+		let before = self.config.layers().to_vec();
+		let result = f( self.config );
+
+		if result.is_err()
+		{
+			self.config.restore_layers( before );
+		}
+
+		result
+	}
+}
+
+
+
+impl<T> Drop for Transaction<'_, T> where T: Clone + DeserializeOwned + Serialize + Debug
+{
+	fn drop( &mut self )
+	{
+		if let Some( backup ) = self.backup.take()
+		{
+			self.config.restore_layers( backup );
+		}
+	}
+}
@@ -0,0 +1,109 @@
+use crate :: { import::*, EkkeResult, Pointer, pointer::{ escape, insert_at, remove_at }, yaml::Tags };
+
+
+// The tag to remove a value set by the layers below, eg. `log_file: !unset`.
+//
+pub( crate ) const UNSET: &str = "!unset";
+
+
+// Take the `!unset` nodes out of data, together with the tags below them. Returns the pointers of the values to remove.
+//
+pub( crate ) fn extract_unsets( mut data: Value, tags: &mut Tags ) -> ( Value, Vec< String > )
+{
+	let unset = tags.take( UNSET );
+
+	for ptr in &unset
+	{
+		remove_at( &mut data, ptr );
+		tags.remove( ptr );
+	}
+
+	( data, unset )
+}
+
+
+// Remove the unset values from settings, before merging the data of a layer into it. Null values in
+// data that override a value in settings remove it as well, so they are taken out of data. Returns the
+// values that are removed.
+//
+pub( crate ) fn apply_unsets( settings: &mut Mapping, data: &mut Mapping, unset: &[ String ] ) -> Vec<( String, Value )>
+{
+	let mut root    = Value::Mapping( std::mem::take( settings ) );
+	let mut layer   = Value::Mapping( std::mem::take( data     ) );
+	let mut removed = Vec::new();
+
+	let nulls: Vec< String > = match &layer
+	{
+		Value::Mapping( map ) => nulls( map, "" ),
+		_                     => Vec::new()     ,
+	};
+
+	let overridden = nulls.into_iter().filter( |ptr| root.jptr( ptr ).map( |v| !v.is_null() ).unwrap_or( false ) );
+
+	for ptr in unset.iter().cloned().chain( overridden.collect::< Vec<_> >() )
+	{
+		remove_at( &mut layer, &ptr );
+
+		if let Some( old ) = remove_at( &mut root, &ptr )
+		{
+			removed.push(( ptr, old ));
+		}
+	}
+
+	if let Value::Mapping( root  ) = root  { *settings = root;  }
+	if let Value::Mapping( layer ) = layer { *data     = layer; }
+
+	removed
+}
+
+
+// Deserializing the settings failed. Find a removed value T can't do without: the first one that
+// makes the settings convert when it is put back. Values that a higher layer set again don't count.
+//
+pub( crate ) fn required<T>( settings: &Mapping, removed: &[( String, Value )], convert: impl Fn( Mapping ) -> EkkeResult<T> ) -> Option< String >
+{
+	let settings = Value::Mapping( settings.clone() );
+
+	removed.iter().find( |( ptr, old )|
+	{
+		if settings.jptr( ptr ).map( |v| !v.is_null() ).unwrap_or( false )
+		{
+			return false;
+		}
+
+		let mut restored = settings.clone();
+
+		match ( insert_at( &mut restored, ptr, old.clone() ), restored )
+		{
+			( Some(()), Value::Mapping( restored ) ) => convert( restored ).is_ok(),
+			_                                        => false,
+		}
+
+	}).map( |( ptr, _ )| ptr.clone() )
+}
+
+
+// The pointers of the null values in the mappings of data.
+//
+fn nulls( data: &Mapping, ptr: &str ) -> Vec< String >
+{
+	let mut out = Vec::new();
+
+	for ( key, value ) in data
+	{
+		let ptr = match key
+		{
+			Value::String( k ) => format!( "{}/{}", ptr, escape( k ) ),
+			_                  => continue,
+		};
+
+		match value
+		{
+			Value::Null           => out.push( ptr ),
+			Value::Mapping( map ) => out.extend( nulls( map, &ptr ) ),
+			_                     => {}
+		}
+	}
+
+	out
+}
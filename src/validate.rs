@@ -0,0 +1,243 @@
+use crate :: { import::*, secret::REDACTED };
+
+
+/// Rules for the settings that go beyond what deserializing checks, like the range of a number or
+/// which values are allowed for a string. Enable them with [`Config::validated`](crate::Config::validated):
+///
+/// ```
+/// # use ekke_config::{ Config, Validate, Validator };
+/// # use std::convert::TryFrom;
+/// #
+/// #[ derive( serde::Serialize, serde::Deserialize, Debug, Clone ) ]
+/// //
+/// struct Settings
+/// {
+///    log_lvl: String     ,
+///    primes : Vec< u32 > ,
+///    min    : u32        ,
+///    max    : u32        ,
+/// }
+///
+/// impl Validate for Settings
+/// {
+///    fn validate( &self, v: &mut Validator )
+///    {
+///       v.one_of   ( "/log_lvl", &self.log_lvl.as_str(), &[ "error", "warn", "info", "debug" ] );
+///       v.non_empty( "/primes" , &self.primes                                                  );
+///       v.range    ( "/max"    , &self.max, 1..=100                                            );
+///
+///       v.check( "/min", self.min <= self.max, "must not be bigger than /max" );
+///    }
+/// }
+///
+/// let mut config: Config<Settings> = Config::try_from( "default: { log_lvl: warn, primes: [ 2 ], min: 1, max: 10 }" )?.validated()?;
+///
+/// assert!( config.merge_runtime( "log_lvl: loud" ).is_err() );
+/// assert_eq!( config.get().log_lvl, "warn" );
+/// # Ok::<(), failure::Error>(())
+/// ```
+///
+pub trait Validate
+{
+	/// Check the settings, reporting what's wrong to `v`.
+	///
+	fn validate( &self, v: &mut Validator );
+}
+
+
+
+/// A value of the settings, or of a layer, that breaks a rule.
+///
+#[ derive( Debug, Clone, PartialEq, Eq ) ]
+//
+pub struct Violation
+{
+	/// The json pointer of the offending value.
+	///
+	pub pointer: String,
+
+	/// What is wrong with it.
+	///
+	pub message: String,
+
+	/// The layer with the offending value, when a layer is checked against a JSON Schema. None for the rules of [`Validate`].
+	///
+	pub layer  : Option< String >,
+
+	/// Where the data of that layer came from, eg. the path of the file, if known.
+	///
+	pub origin : Option< String >,
+}
+
+
+impl fmt::Display for Violation
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "{}: {}", self.pointer, self.message )?;
+
+		match ( &self.layer, &self.origin )
+		{
+			( Some( layer ), Some( origin ) ) => write!( f, " (layer {}, from {})", layer, origin ),
+			( Some( layer ), None           ) => write!( f, " (layer {})"         , layer         ),
+			_                                 => Ok(()),
+		}
+	}
+}
+
+
+
+/// All rules the settings break, see [`EkkeCfgError::Invalid`](crate::EkkeCfgError::Invalid).
+///
+#[ derive( Debug, Clone, Default, PartialEq, Eq ) ]
+//
+pub struct Violations( pub( crate ) Vec< Violation > );
+
+
+impl Violations
+{
+	// Replace the messages of violations at sensitive pointers.
+	//
+	pub( crate ) fn redact( &mut self, sensitive: impl Fn( &str ) -> bool )
+	{
+		for violation in self.0.iter_mut().filter( |v| sensitive( &v.pointer ) )
+		{
+			violation.message = format!( "{} is not valid", REDACTED );
+		}
+	}
+}
+
+
+impl std::ops::Deref for Violations
+{
+	type Target = [ Violation ];
+
+	fn deref( &self ) -> &[ Violation ]
+	{
+		&self.0
+	}
+}
+
+
+impl fmt::Display for Violations
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		let all: Vec< String > = self.0.iter().map( Violation::to_string ).collect();
+
+		write!( f, "{}", all.join( "; " ) )
+	}
+}
+
+
+
+/// Collects the rules the settings break. It's passed to [`Validate::validate`], which calls the helpers for each value
+/// it checks. Every helper takes the json pointer of the value, which is reported with the violation.
+///
+#[ derive( Debug, Clone, Default ) ]
+//
+pub struct Validator
+{
+	violations: Vec< Violation >,
+}
+
+
+impl Validator
+{
+	/// A validator without violations.
+	///
+	pub fn new() -> Self
+	{
+		Self::default()
+	}
+
+
+	/// Report a violation.
+	///
+	pub fn error( &mut self, ptr: impl Into<String>, message: impl Into<String> ) -> &mut Self
+	{
+		self.violations.push( Violation { pointer: ptr.into(), message: message.into(), layer: None, origin: None } );
+		self
+	}
+
+
+	/// Report a violation unless `ok` holds. For rules about several values, like one being smaller than another.
+	///
+	pub fn check( &mut self, ptr: impl Into<String>, ok: bool, message: impl Into<String> ) -> &mut Self
+	{
+		if !ok { self.error( ptr, message ); }
+		self
+	}
+
+
+	/// The value must lie within range, eg. `1..=100` or `0.0..1.0`.
+	///
+	pub fn range<V, R>( &mut self, ptr: impl Into<String>, value: &V, range: R ) -> &mut Self
+
+		where V: PartialOrd + Debug, R: RangeBounds<V> + Debug
+	{
+		let ok = range.contains( value );
+
+		self.check( ptr, ok, format!( "{:?} is not in the range {:?}", value, range ) )
+	}
+
+
+	/// The value must be one of the allowed ones.
+	///
+	pub fn one_of<V>( &mut self, ptr: impl Into<String>, value: &V, allowed: &[V] ) -> &mut Self
+
+		where V: PartialEq + Debug
+	{
+		let ok = allowed.contains( value );
+
+		self.check( ptr, ok, format!( "{:?} is not one of {:?}", value, allowed ) )
+	}
+
+
+	/// The collection must not be empty. This takes anything that can be iterated, like `&Vec` or `&HashMap`.
+	/// For strings, pass `s.chars()`.
+	///
+	pub fn non_empty<C>( &mut self, ptr: impl Into<String>, collection: C ) -> &mut Self
+
+		where C: IntoIterator
+	{
+		let ok = collection.into_iter().next().is_some();
+
+		self.check( ptr, ok, "must not be empty" )
+	}
+
+
+	/// The string must match a regular expression. It can match anywhere in the string, so anchor it with `^` and `$`
+	/// to match the whole string. An invalid expression is reported as a violation.
+	///
+	pub fn matches( &mut self, ptr: impl Into<String>, value: &str, regex: &str ) -> &mut Self
+	{
+		match Regex::new( regex )
+		{
+			Ok ( re ) => self.check( ptr, re.is_match( value ), format!( "{:?} does not match {}", value, regex ) ),
+			Err( e  ) => self.error( ptr, format!( "invalid regular expression {}: {}", regex, e ) ),
+		}
+	}
+
+
+	/// The violations reported so far.
+	///
+	pub fn violations( &self ) -> &[ Violation ]
+	{
+		&self.violations
+	}
+
+
+	/// Whether no violations have been reported.
+	///
+	pub fn is_valid( &self ) -> bool
+	{
+		self.violations.is_empty()
+	}
+
+
+	pub( crate ) fn into_violations( self ) -> Violations
+	{
+		Violations( self.violations )
+	}
+}
@@ -0,0 +1,118 @@
+use crate :: { import::*, Layer };
+
+
+/// The directories where configuration files for an application are looked for, according to the
+/// [XDG base directory specification](https://specifications.freedesktop.org/basedir-spec/latest/).
+///
+/// From lowest to highest precedence:
+///
+/// - `/etc/<app>`
+/// - `<dir>/<app>` for every entry in `$XDG_CONFIG_DIRS` (defaults to `/etc/xdg`), the first entry being most important
+/// - `$XDG_CONFIG_HOME/<app>`, which defaults to `~/.config/<app>`
+///
+/// As required by the spec, relative paths in the environment variables are ignored.
+///
+#[ derive( Debug, Clone, PartialEq, Eq ) ]
+//
+pub struct XdgDirs
+{
+	system: PathBuf          ,
+	dirs  : Vec< PathBuf >   ,
+	user  : Option< PathBuf >,
+}
+
+
+impl XdgDirs
+{
+	/// Find the directories for `app` from the current environment.
+	///
+	pub fn new( app: &str ) -> Self
+	{
+		let system = Path::new( "/etc" ).join( app );
+
+		let dirs = env_path( "XDG_CONFIG_DIRS" )
+
+			.map( |dirs| env::split_paths( &dirs ).filter( |d| d.is_absolute() ).collect() )
+			.unwrap_or_else( || vec![ PathBuf::from( "/etc/xdg" ) ] )
+
+			.into_iter()
+			.map( |dir| dir.join( app ) )
+			.collect()
+		;
+
+		let user = base_dir( "XDG_CONFIG_HOME", ".config" ).map( |dir| dir.join( app ) );
+
+		Self { system, dirs, user }
+	}
+
+
+	/// The user configuration directory, if the home directory is known.
+	///
+	pub fn user( &self ) -> Option< &Path >
+	{
+		self.user.as_deref()
+	}
+
+
+	/// The system configuration directories, from lowest to highest precedence.
+	///
+	pub fn system( &self ) -> Vec< &Path >
+	{
+		let mut system = vec![ self.system.as_path() ];
+
+		system.extend( self.dirs.iter().rev().map( PathBuf::as_path ) );
+
+		system
+	}
+
+
+	/// All places where `file` might be found, from lowest to highest precedence, together with the
+	/// name of the layer they should produce. The system files are named `system` for `/etc/<app>`
+	/// and `xdg:<dir>` for entries of `$XDG_CONFIG_DIRS`. The user file is named [`Layer::USERSET`].
+	///
+	pub fn candidates( &self, file: &str ) -> Vec<( String, PathBuf )>
+	{
+		let mut candidates = vec![ ( "system".to_string(), self.system.join( file ) ) ];
+
+		for dir in self.dirs.iter().rev()
+		{
+			candidates.push(( format!( "xdg:{}", dir.to_string_lossy() ), dir.join( file ) ));
+		}
+
+		if let Some( user ) = &self.user
+		{
+			candidates.push(( Layer::USERSET.to_string(), user.join( file ) ));
+		}
+
+		candidates
+	}
+
+
+	/// The paths of the candidates for `file` that exist, from lowest to highest precedence.
+	///
+	pub fn find( &self, file: &str ) -> Vec< PathBuf >
+	{
+		self.candidates( file ).into_iter().map( |(_, path)| path ).filter( |path| path.is_file() ).collect()
+	}
+}
+
+
+// Get one of the XDG user base directories, like XDG_CONFIG_HOME, falling back to `default`
+// relative to the home directory.
+//
+pub( crate ) fn base_dir( var: &str, default: &str ) -> Option< PathBuf >
+{
+	env_path( var )
+
+		.map( PathBuf::from )
+		.filter( |dir| dir.is_absolute() )
+		.or_else( || env_path( "HOME" ).map( |home| Path::new( &home ).join( default ) ) )
+}
+
+
+// Get an environment variable that is set and not empty.
+//
+fn env_path( var: &str ) -> Option< OsString >
+{
+	env::var_os( var ).filter( |value| !value.is_empty() )
+}
@@ -0,0 +1,536 @@
+//! A yaml loader that keeps custom tags.
+//!
+//! serde_yaml silently drops tags like `!include path`, so ekke_config parses yaml with this loader instead.
+//! The data holds the tagged nodes without their tags, and the tags are kept apart, by the json pointer of
+//! the node. Thus `db_path: !include path.yml` is loaded as `db_path: path.yml` with the tag `!include` at
+//! `/db_path`. Since only this loader produces tags, data from elsewhere, like environment variables or
+//! serialized values, can never pose as a tag.
+//!
+//! Tags on mapping keys, and on the nodes below keys that are not strings, are ignored. Core tags, like `!!str`
+//! or `!!int`, are applied as usual. An alias gets the tags of its anchor that produce the value, like `!file`, but not
+//! the ones about the key, like `!locked`, `!unset` or merge directives, so `*x` is not locked when `!locked &x` is.
+//! Integers that don't fit in 64 bits are an error, like for serde_yaml.
+//
+use crate :: { import::*, EkkeResult, EkkeCfgError, Pointer, lock::LOCKED, pointer::{ escape, unescape, within }, strategy::is_directive, unset::UNSET };
+
+use yaml_rust ::
+{
+	Yaml,
+	parser  :: { Event, MarkedEventReceiver, Parser   } ,
+	scanner :: { Marker, Scanner, TScalarStyle, TokenType } ,
+};
+
+
+/// The tag to include other files.
+//
+pub( crate ) const INCLUDE: &str = "!include";
+
+
+/// Parsed yaml text, together with its custom tags, like `!include` or `!locked`. Custom
+/// [`Source`](crate::Source)s that read yaml return this from [`Source::load_document`](crate::Source::load_document),
+/// so the tags in it work.
+///
+/// Tags can only come from yaml text. A document made from a [`Mapping`] has none.
+///
+#[ derive( Debug, Clone, Default, PartialEq, Eq ) ]
+//
+pub struct Document
+{
+	data: Value,
+	tags: Tags ,
+}
+
+
+impl Document
+{
+	/// Parse yaml text. The root must be a mapping, or a tagged node like `!include other.yml`. An empty
+	/// document is an empty mapping.
+	///
+	pub fn parse( input: &str ) -> EkkeResult< Self >
+	{
+		let ( data, tags ) = parse_tagged( input )?;
+
+		match data
+		{
+			Value::Mapping(_)             => Ok( Self { data, tags } ),
+			Value::Null                   => Ok( Self { data: Mapping::new().into(), tags } ),
+			_ if tags.get( "" ).is_some() => Ok( Self { data, tags } ),
+
+			_ => Err( EkkeCfgError::ConfigParse.context( "The root of a configuration must be a mapping." ).into() ),
+		}
+	}
+
+
+	/// The data without the tags, as a mapping.
+	///
+	pub fn into_data( self ) -> EkkeResult< Mapping >
+	{
+		match self.data
+		{
+			Value::Mapping( data ) => Ok( data ),
+			_                      => Err( EkkeCfgError::ConfigParse.context( "The root of a configuration must be a mapping." ).into() ),
+		}
+	}
+
+
+	pub( crate ) fn into_parts( self ) -> ( Value, Tags )
+	{
+		( self.data, self.tags )
+	}
+
+
+	pub( crate ) fn from_parts( data: Value, tags: Tags ) -> Self
+	{
+		Self { data, tags }
+	}
+
+
+	// Merge another document into this one. The tags of the values other replaces are dropped.
+	//
+	pub( crate ) fn merge( &mut self, other: Document ) -> MergeResult<()>
+	{
+		let Document { data, tags } = other;
+
+		let old = &self.data;
+
+		self.tags.0.retain( |( ptr, _ )| tags.get( ptr ).is_none() && !replaces( old, &data, ptr ) );
+		self.tags.0.extend( tags.0 );
+
+		match ( &mut self.data, data )
+		{
+			( Value::Mapping( old ), Value::Mapping( new ) ) => old.merge( new ),
+			( old                  , new                   ) => { *old = new; Ok(()) }
+		}
+	}
+}
+
+
+impl From< Mapping > for Document
+{
+	fn from( data: Mapping ) -> Self
+	{
+		Self { data: Value::Mapping( data ), tags: Tags::default() }
+	}
+}
+
+
+
+// The custom tags of a yaml document: the json pointer of each tagged node with its tag, parents
+// before children.
+//
+#[ derive( Debug, Clone, Default, PartialEq, Eq ) ]
+//
+pub( crate ) struct Tags( Vec<( String, String )> );
+
+
+impl Tags
+{
+	pub( crate ) fn iter( &self ) -> impl Iterator< Item = &( String, String ) >
+	{
+		self.0.iter()
+	}
+
+
+	// The tag of the node at ptr.
+	//
+	pub( crate ) fn get( &self, ptr: &str ) -> Option< &str >
+	{
+		self.0.iter().find( |( p, _ )| p == ptr ).map( |( _, tag )| tag.as_str() )
+	}
+
+
+	// Take out the nodes with the given tag. Returns their json pointers.
+	//
+	pub( crate ) fn take( &mut self, tag: &str ) -> Vec< String >
+	{
+		let mut found = Vec::new();
+
+		self.0.retain( |( ptr, t )| if t == tag { found.push( ptr.clone() ); false } else { true } );
+
+		found
+	}
+
+
+	// Forget the tags at or below ptr, because the value there is gone.
+	//
+	pub( crate ) fn remove( &mut self, ptr: &str )
+	{
+		self.0.retain( |( p, _ )| !within( p, ptr ) );
+	}
+
+
+	// The tags at or below ptr, relative to it.
+	//
+	pub( crate ) fn below( &self, ptr: &str ) -> Self
+	{
+		Self( self.0.iter().filter( |( p, _ )| within( p, ptr ) ).map( |( p, tag )| ( p[ ptr.len().. ].to_string(), tag.clone() ) ).collect() )
+	}
+
+
+	// Add the tags of a node that is put at ptr.
+	//
+	pub( crate ) fn nest( &mut self, ptr: &str, tags: Tags )
+	{
+		self.0.extend( tags.0.into_iter().map( |( p, tag )| ( format!( "{}{}", ptr, p ), tag ) ) );
+	}
+}
+
+
+
+/// Parse a yaml document into a Value and its tags.
+//
+pub( crate ) fn parse_tagged( input: &str ) -> EkkeResult<( Value, Tags )>
+{
+	let mut loader = Loader
+	{
+		tags   : collection_tags( input ),
+		stack  : Vec::new()              ,
+		anchors: HashMap::new()          ,
+		docs   : Vec::new()              ,
+		error  : None                    ,
+	};
+
+	Parser::new( input.chars() ).load( &mut loader, true )?;
+
+	if let Some( err ) = loader.error
+	{
+		return Err( err );
+	}
+
+	match loader.docs.len()
+	{
+		0 => Ok(( Value::Null, Tags::default() )),
+		1 => Ok( loader.docs.remove(0) ),
+
+		_ => Err( EkkeCfgError::ConfigParse.context( "Yaml input must contain a single document." ).into() ),
+	}
+}
+
+
+/// Parse a yaml document into a Value, ignoring custom tags.
+//
+#[ cfg( feature = "schema" ) ]
+//
+pub( crate ) fn parse( input: &str ) -> EkkeResult< Value >
+{
+	Ok( parse_tagged( input )?.0 )
+}
+
+
+// Whether merging new into old replaces the value at ptr in old, rather than merging into it: new has
+// something other than a mapping at ptr or above it, or has a value at ptr where old has no mapping.
+//
+fn replaces( old: &Value, new: &Value, ptr: &str ) -> bool
+{
+	let mut node = new;
+
+	for token in ptr.split( '/' ).skip( 1 )
+	{
+		match node
+		{
+			Value::Mapping( map ) => match map.get( &unescape( token ).into() )
+			{
+				Some( next ) => node = next,
+				None         => return false,
+			}
+
+			_ => return true,
+		}
+	}
+
+	!( node.is_mapping() && old.jptr( ptr ).map( Value::is_mapping ).unwrap_or( false ) )
+}
+
+
+
+// The parser of yaml_rust only reports tags for scalars. The scanner does see the tags of collections,
+// so we scan the input first to find the positions of tags that are followed by a collection.
+//
+fn collection_tags( input: &str ) -> VecDeque<( usize, String )>
+{
+	let mut tags    = VecDeque::new();
+	let mut pending = None;
+
+	for token in Scanner::new( input.chars() )
+	{
+		match token.1
+		{
+			TokenType::Anchor(_) => continue,
+
+			TokenType::Tag( handle, suffix ) =>
+			{
+				pending = if handle == "!" { Some(( token.0.index(), format!( "!{}", suffix ) )) } else { None };
+				continue;
+			}
+
+			TokenType::FlowSequenceStart  |
+			TokenType::FlowMappingStart   |
+			TokenType::BlockSequenceStart |
+			TokenType::BlockMappingStart  |
+			TokenType::BlockEntry         =>
+			{
+				if let Some( tag ) = pending.take() { tags.push_back( tag ); }
+			}
+
+			_ => pending = None,
+		}
+	}
+
+	tags
+}
+
+
+
+struct Loader
+{
+	tags   : VecDeque<( usize, String )>     ,
+	stack  : Vec< Node >                    ,
+	anchors: HashMap< usize, ( Value, Tags ) >,
+	docs   : Vec<( Value, Tags )>            ,
+
+	// The first scalar that could not be converted, since events can't return errors.
+	//
+	error  : Option< Error >                ,
+}
+
+
+// A collection under construction, with the tags found in it so far.
+//
+enum Node
+{
+	Seq( Vec< Value >                    , Tags, usize, Option< String > ),
+	Map( Mapping      , Option< Value >  , Tags, usize, Option< String > ),
+}
+
+
+impl Loader
+{
+	// The collection tag for a node starting at mark, if any.
+	//
+	fn take_tag( &mut self, mark: &Marker ) -> Option< String >
+	{
+		match self.tags.front()
+		{
+			Some(( pos, _ )) if *pos < mark.index() => self.tags.pop_front().map( |(_, tag)| tag ),
+			_                                       => None                                       ,
+		}
+	}
+
+
+	// A node is complete, tag it, remember it's anchor and add it to the parent. The tags of
+	// the node and the nodes below it are moved to the parent, relative to it. Anchors don't keep
+	// the tags about keys, so aliases don't get them.
+	//
+	fn complete( &mut self, value: Value, mut tags: Tags, anchor: usize, tag: Option< String > )
+	{
+		if let Some( tag ) = tag
+		{
+			tags.0.insert( 0, ( String::new(), tag ) );
+		}
+
+		if anchor > 0
+		{
+			let kept = Tags( tags.0.iter().filter( |( _, tag )| !about_key( tag ) ).cloned().collect() );
+
+			self.anchors.insert( anchor, ( value.clone(), kept ) );
+		}
+
+		match self.stack.last_mut()
+		{
+			None => self.docs.push(( value, tags )),
+
+			Some( Node::Seq( seq, found, .. ) ) =>
+			{
+				found.nest( &format!( "/{}", seq.len() ), tags );
+				seq.push( value );
+			}
+
+			// The tags of keys are dropped.
+			//
+			Some( Node::Map( map, key, found, .. ) ) => match key.take()
+			{
+				None => *key = Some( value ),
+
+				Some( key ) =>
+				{
+					if let Value::String( k ) = &key
+					{
+						let ptr = format!( "/{}", escape( k ) );
+
+						found.remove( &ptr );
+						found.nest  ( &ptr, tags );
+					}
+
+					map.insert( key, value );
+				}
+			}
+		}
+	}
+}
+
+
+impl MarkedEventReceiver for Loader
+{
+	fn on_event( &mut self, event: Event, mark: Marker )
+	{
+		match event
+		{
+			Event::Scalar( text, style, anchor, token ) =>
+			{
+				// Scalars report their own tags.
+				//
+				self.take_tag( &mark );
+
+				let ( value, tag ) = match scalar( text, style, token )
+				{
+					Ok( scalar ) => scalar,
+
+					Err( err ) =>
+					{
+						let line = mark.line();
+						self.error.get_or_insert_with( || err.context( format!( "Invalid yaml value at line {}", line ) ).into() );
+
+						( Value::Null, None )
+					}
+				};
+
+				self.complete( value, Tags::default(), anchor, tag );
+			}
+
+			Event::Alias( id ) =>
+			{
+				self.take_tag( &mark );
+
+				let ( value, tags ) = self.anchors.get( &id ).cloned().unwrap_or_default();
+
+				self.complete( value, tags, 0, None );
+			}
+
+			Event::SequenceStart( anchor ) =>
+			{
+				let tag = self.take_tag( &mark );
+				self.stack.push( Node::Seq( Vec::new(), Tags::default(), anchor, tag ) );
+			}
+
+			Event::MappingStart( anchor ) =>
+			{
+				let tag = self.take_tag( &mark );
+				self.stack.push( Node::Map( Mapping::new(), None, Tags::default(), anchor, tag ) );
+			}
+
+			Event::SequenceEnd | Event::MappingEnd =>
+			{
+				match self.stack.pop()
+				{
+					Some( Node::Seq( seq, tags, anchor, tag )    ) => self.complete( Value::Sequence( seq ), tags, anchor, tag ),
+					Some( Node::Map( map, _, tags, anchor, tag ) ) => self.complete( Value::Mapping ( map ), tags, anchor, tag ),
+					None                                           => {}
+				}
+			}
+
+			_ => {}
+		}
+	}
+}
+
+
+// Whether a tag is about the key a node is at, rather than about its value.
+//
+fn about_key( tag: &str ) -> bool
+{
+	tag == LOCKED || tag == UNSET || is_directive( tag )
+}
+
+
+// Convert a scalar to a value, resolving it's type like serde_yaml does. Returns the custom tag if there is one.
+//
+fn scalar( text: String, style: TScalarStyle, token: Option< TokenType > ) -> EkkeResult<( Value, Option< String > )>
+{
+	let ( handle, suffix ) = match token
+	{
+		Some( TokenType::Tag( handle, suffix ) ) => ( handle, suffix ),
+		_                                        => Default::default(),
+	};
+
+	if handle == "!"
+	{
+		let value = if style == TScalarStyle::Plain { infer( &text )? } else { Value::String( text ) };
+
+		// An empty node with only a tag, like `key: !unset`.
+		//
+		let value = if value == Value::String( String::new() ) && style == TScalarStyle::Plain { Value::Null } else { value };
+
+		return Ok(( value, Some( format!( "!{}", suffix ) ) ));
+	}
+
+	// Core tags set the type, whatever the style.
+	//
+	if handle == "!!"
+	{
+		let invalid = |kind: &str| EkkeCfgError::ConfigParse.context( format!( "Invalid value for {}: {}", kind, text ) );
+
+		let value = match suffix.as_str()
+		{
+			"bool"  => Value::Bool( text.parse().map_err( |_| invalid( "!!bool"  ) )? ),
+			"int"   => Value::from( text.parse::<i64>().map_err( |_| invalid( "!!int"   ) )? ),
+			"float" => Value::from( text.parse::<f64>().map_err( |_| invalid( "!!float" ) )? ),
+
+			"null" => match text.as_str()
+			{
+				"~" | "null" => Value::Null,
+				_            => return Err( invalid( "!!null" ).into() ),
+			}
+
+			_ => Value::String( text.clone() ),
+		};
+
+		return Ok(( value, None ));
+	}
+
+	if style != TScalarStyle::Plain
+	{
+		return Ok(( Value::String( text ), None ));
+	}
+
+	Ok(( infer( &text )?, None ))
+}
+
+
+/// Interpret text like a plain yaml scalar, so numbers, booleans and null get their type. yaml_rust only
+/// knows i64, keeps special floats as text and takes words like `inf` for floats, so floats are handled here
+/// like serde_yaml does. Like for serde_yaml, integers that don't fit in 64 bits are an error rather than a float.
+//
+pub( crate ) fn infer( text: &str ) -> EkkeResult< Value >
+{
+	match text
+	{
+		".inf" | ".Inf" | ".INF" | "+.inf" | "+.Inf" | "+.INF" => return Ok( Value::from( f64::INFINITY     ) ),
+		"-.inf" | "-.Inf" | "-.INF"                            => return Ok( Value::from( f64::NEG_INFINITY ) ),
+		".nan" | ".NaN" | ".NAN"                               => return Ok( Value::from( f64::NAN          ) ),
+		_                                                      => {}
+	}
+
+	let value = match Yaml::from_str( text )
+	{
+		Yaml::Integer( i ) => Value::Number( i.into() ),
+		Yaml::Boolean( b ) => Value::Bool  ( b        ),
+		Yaml::Null         => Value::Null             ,
+
+		Yaml::Real( r ) => match ( r.parse::<u64>(), r.parse::<f64>() )
+		{
+			( Ok( u ), _ ) => Value::from( u ),
+
+			_ if r.parse::<u128>().is_ok() || r.parse::<i128>().is_ok() =>
+			{
+				return Err( EkkeCfgError::ConfigParse.context( format!( "Integer does not fit in 64 bits: {}", r ) ).into() );
+			}
+
+			( _, Ok( f ) ) if f.is_finite() => Value::from( f ),
+			_                               => Value::String( r ),
+		}
+
+		_ => Value::String( text.to_string() ),
+	};
+
+	Ok( value )
+}
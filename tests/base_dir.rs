@@ -0,0 +1,97 @@
+use ekke_config :: { Config, EkkeCfgError              } ;
+use std         :: { convert::TryFrom, env, path::Path } ;
+
+mod common;
+use common::*;
+
+
+const DEFAULT: &str =
+"
+userset: userset.yml
+
+default:
+  my_app:
+    db_path: data/db.sqlite
+    log_lvl: debug
+
+  other_comp:
+    primes: [ 1, 3, 5, 7 ]
+    algo  : fournier
+";
+
+
+
+#[ test ] fn test_base_dir()
+{
+	let cfg: Config<Settings> = Config::from_defaults( DEFAULT, Some( Path::new( "data" ) ) ).unwrap();
+
+	assert_eq!( cfg.usr_path().clone().unwrap(), Path::new( "data/userset.yml" ) );
+	assert_eq!( cfg.get().my_app.log_lvl       , "warn"                          );
+}
+
+
+#[ test ] fn test_cwd()
+{
+	// Without a base dir, the path is relative to the working directory, which for tests is the crate root.
+	//
+	assert!( Config::<Settings>::try_from( DEFAULT ).is_err() );
+	assert!( Config::<Settings>::from_defaults( DEFAULT, None ).is_err() );
+}
+
+
+#[ test ] fn test_file()
+{
+	// data/defaults.yml has a relative userset path.
+	//
+	let cfg: Config<Settings> = Config::try_from( Path::new( "data/defaults.yml" ) ).unwrap();
+
+	assert_eq!( cfg.usr_path().clone().unwrap(), Path::new( "data/userset.yml" ) );
+	assert_eq!( cfg.get().other_comp.algo      , "euler"                         );
+}
+
+
+#[ test ] fn test_absolute()
+{
+	let input = DEFAULT.replace( "userset.yml", concat!( env!( "CARGO_MANIFEST_DIR" ), "/data/userset.yml" ) );
+	let cfg: Config<Settings> = Config::from_defaults( &input, Some( Path::new( "/does/not/exist" ) ) ).unwrap();
+
+	assert_eq!( cfg.get().other_comp.algo, "euler" );
+}
+
+
+#[ test ] fn test_placeholders()
+{
+	env::set_var( "EKKE_PLACEHOLDER_TEST", "data" );
+
+	for path in &[ "${DEFAULTS_DIR}/userset.yml", "$EKKE_PLACEHOLDER_TEST/userset.yml", "${EKKE_PLACEHOLDER_TEST}/userset.yml" ]
+	{
+		let input = DEFAULT.replace( "userset.yml", path );
+		let base  = if path.contains( "DEFAULTS_DIR" ) { Some( Path::new( "data" ) ) } else { None };
+
+		let cfg: Config<Settings> = Config::from_defaults( &input, base ).unwrap();
+
+		assert!   ( cfg.usr_path().clone().unwrap().ends_with( "data/userset.yml" ) );
+		assert_eq!( cfg.get().other_comp.algo, "euler" );
+	}
+}
+
+
+#[ test ] fn test_unset()
+{
+	let input = DEFAULT.replace( "userset.yml", "${EKKE_PLACEHOLDER_UNSET}/userset.yml" );
+	let err   = Config::<Settings>::from_defaults( &input, None ).unwrap_err();
+
+	let var = err.iter_chain().find_map( |e| match e.downcast_ref::<EkkeCfgError>()
+	{
+		Some( EkkeCfgError::UnsetVariable( var ) ) => Some( var.clone() ),
+		_                                          => None              ,
+	});
+
+	assert_eq!( var, Some( "EKKE_PLACEHOLDER_UNSET".to_string() ) );
+
+	// DEFAULTS_DIR is unset when there is no base dir.
+	//
+	let input = DEFAULT.replace( "userset.yml", "${DEFAULTS_DIR}/userset.yml" );
+
+	assert!( Config::<Settings>::from_defaults( &input, None ).is_err() );
+}
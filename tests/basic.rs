@@ -79,3 +79,34 @@ use common::*;
 	assert_eq!( cfg.usr_path().clone().unwrap().to_str().unwrap(), "data/userset.yml" );
 	assert_eq!( cfg.def_path().clone().unwrap().to_str().unwrap(), "data/defaults.yml" );
 }
+
+
+#[ test ] fn test_numbers()
+{
+	#[ derive( serde::Serialize, serde::Deserialize, Debug, Clone ) ]
+	//
+	struct Numbers
+	{
+		big : u64   ,
+		inf : f64   ,
+		ninf: f64   ,
+		nan : f64   ,
+		half: f64   ,
+		word: String,
+	}
+
+	let cfg: ekke_config::Config<Numbers> = std::convert::TryFrom::try_from
+	(
+		"default: { big: 18446744073709551615, inf: .inf, ninf: -.Inf, nan: .NaN, half: 0.5, word: infinity }"
+
+	).unwrap();
+
+	assert_eq!( cfg.get().big , u64::MAX          );
+	assert_eq!( cfg.get().inf , f64::INFINITY     );
+	assert_eq!( cfg.get().ninf, f64::NEG_INFINITY );
+	assert_eq!( cfg.get().half, 0.5               );
+	assert_eq!( cfg.get().word, "infinity"        );
+	assert!   ( cfg.get().nan.is_nan()            );
+
+	assert_eq!( cfg.default().jptr( "/big" ).unwrap().as_u64(), Some( u64::MAX ) );
+}
@@ -0,0 +1,186 @@
+use serde_yaml  :: { Mapping, Value                         } ;
+use ekke_config :: { Config, ConfigBuilder, Layer, Pointer } ;
+use std         :: { env                                    } ;
+
+mod common;
+use common::*;
+
+
+const DEFAULT: &str =
+"
+my_app:
+  db_path: data/db.sqlite
+  log_lvl: debug
+
+other_comp:
+  primes: [ 1, 3, 5, 7 ]
+  algo  : fournier
+";
+
+
+
+#[ test ] fn test_order()
+{
+	let cfg: Config<Settings> = ConfigBuilder::new()
+
+		.string       ( Layer::DEFAULT, DEFAULT                   )
+		.optional_file( "system"      , "data/does_not_exist.yml" )
+		.dir          ( "system.d"    , "data/conf.d"             )
+		.file         ( Layer::USERSET, "data/userset.yml"        )
+
+		.build().unwrap();
+
+	let names: Vec<&str> = cfg.layers().iter().map( Layer::name ).collect();
+
+	assert_eq!( names, vec![ "default", "system.d", "userset" ] );
+
+	assert_eq!( cfg.layer( "system.d" ).unwrap().origin(), Some( "data/conf.d"      ) );
+	assert_eq!( cfg.layer( "userset"  ).unwrap().origin(), Some( "data/userset.yml" ) );
+
+	assert_eq!( cfg.default().jptr( "/my_app/log_lvl" ).unwrap(), "debug" );
+	assert_eq!( cfg.get().my_app.log_lvl   , "warn"                 );
+	assert_eq!( cfg.get().other_comp.algo  , "euler"                );
+	assert_eq!( cfg.get().other_comp.primes, vec![ 1, 3, 5, 7, 11 ] );
+	assert_eq!( cfg.runtime()              , None                   );
+}
+
+
+#[ test ] fn test_dir()
+{
+	let cfg: Config<Settings> = ConfigBuilder::new()
+
+		.string( Layer::DEFAULT, DEFAULT       )
+		.dir   ( "system.d"    , "data/conf.d" )
+
+		.build().unwrap();
+
+	assert_eq!( cfg.get().my_app.log_lvl , "info"  );
+	assert_eq!( cfg.get().other_comp.algo, "gauss" );
+}
+
+
+#[ test ] fn test_required()
+{
+	let cfg = ConfigBuilder::new()
+
+		.string( Layer::DEFAULT, DEFAULT                   )
+		.file  ( "system"      , "data/does_not_exist.yml" )
+
+		.build::<Settings>();
+
+	assert!( cfg.is_err() );
+}
+
+
+#[ test ] fn test_env_cli()
+{
+	env::set_var( "EKKE_BUILDER_TEST_MY_APP__LOG_LVL"   , "error"    );
+	env::set_var( "EKKE_BUILDER_TEST_OTHER_COMP__PRIMES", "[ 2, 3 ]" );
+
+	let cfg: Config<Settings> = ConfigBuilder::new()
+
+		.string( Layer::DEFAULT, DEFAULT                         )
+		.env   ( "env"         , "EKKE_BUILDER_TEST_"            )
+		.cli   ( "cli"         , vec![ "other_comp.algo=euler" ] )
+
+		.build().unwrap();
+
+	assert_eq!( cfg.get().my_app.log_lvl   , "error"      );
+	assert_eq!( cfg.get().other_comp.primes, vec![ 2, 3 ] );
+	assert_eq!( cfg.get().other_comp.algo  , "euler"      );
+
+	assert!( ConfigBuilder::new().string( Layer::DEFAULT, DEFAULT ).cli( "cli", vec![ "algo" ] ).build::<Settings>().is_err() );
+}
+
+
+#[ test ] fn test_env_cli_empty()
+{
+	// Empty values are empty strings, they don't unset anything.
+	//
+	env::set_var( "EKKE_BUILDER_EMPTY_MY_APP__LOG_LVL", "" );
+
+	let cfg: Config<Settings> = ConfigBuilder::new()
+
+		.string( Layer::DEFAULT, DEFAULT                 )
+		.env   ( "env"         , "EKKE_BUILDER_EMPTY_"   )
+		.cli   ( "cli"         , vec![ "other_comp.algo=" ]    )
+
+		.build().unwrap();
+
+	assert_eq!( cfg.get().my_app.log_lvl , "" );
+	assert_eq!( cfg.get().other_comp.algo, "" );
+}
+
+
+#[ test ] fn test_env_cli_literal()
+{
+	// Values from the environment and the command line are not interpolated.
+	//
+	env::set_var( "EKKE_BUILDER_LITERAL_MY_APP__LOG_LVL", "a${b}" );
+
+	let cfg: Config<Settings> = ConfigBuilder::new()
+
+		.string( Layer::DEFAULT, DEFAULT                          )
+		.env   ( "env"         , "EKKE_BUILDER_LITERAL_"          )
+		.cli   ( "cli"         , vec![ "other_comp.algo=c$${d}" ] )
+
+		.build().unwrap();
+
+	assert_eq!( cfg.get().my_app.log_lvl , "a${b}"  );
+	assert_eq!( cfg.get().other_comp.algo, "c$${d}" );
+}
+
+
+#[ cfg( unix ) ]
+//
+#[ test ] fn test_env_not_unicode()
+{
+	use std::{ ffi::OsString, os::unix::ffi::OsStringExt };
+
+	// A name that isn't unicode is ignored, a value that isn't unicode is an error.
+	//
+	env::set_var( OsString::from_vec( b"EKKE_BUILDER_UNICODE_\xff".to_vec() ), "x" );
+	env::set_var( "EKKE_BUILDER_UNICODE_MY_APP__LOG_LVL", "error" );
+
+	let cfg: Config<Settings> = ConfigBuilder::new()
+
+		.string( Layer::DEFAULT, DEFAULT                 )
+		.env   ( "env"         , "EKKE_BUILDER_UNICODE_" )
+
+		.build().unwrap();
+
+	assert_eq!( cfg.get().my_app.log_lvl, "error" );
+
+	env::set_var( "EKKE_BUILDER_UNICODE_MY_APP__LOG_LVL", OsString::from_vec( b"\xff".to_vec() ) );
+
+	assert!( ConfigBuilder::new().string( Layer::DEFAULT, DEFAULT ).env( "env", "EKKE_BUILDER_UNICODE_" ).build::<Settings>().is_err() );
+}
+
+
+#[ test ] fn test_value()
+{
+	let mut data = Mapping::new();
+	let mut app  = Mapping::new();
+
+	app .insert( "log_lvl".into(), "trace".into()        );
+	data.insert( "my_app" .into(), Value::Mapping( app ) );
+
+	let mut cfg: Config<Settings> = ConfigBuilder::new()
+
+		.string( Layer::DEFAULT, DEFAULT )
+		.value ( "app"         , data    )
+
+		.build().unwrap();
+
+	assert_eq!( cfg.get().my_app.log_lvl, "trace" );
+
+	// The convenience methods still work and runtime goes on top.
+	//
+	cfg.merge_runtime( "my_app: { log_lvl: info }"  ).unwrap();
+	cfg.merge_userset( "my_app: { log_lvl: error }" ).unwrap();
+
+	let names: Vec<&str> = cfg.layers().iter().map( Layer::name ).collect();
+
+	assert_eq!( names, vec![ "default", "app", "userset", "runtime" ] );
+	assert_eq!( cfg.get().my_app.log_lvl, "info" );
+}
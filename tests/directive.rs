@@ -0,0 +1,104 @@
+use ekke_config :: { Config, ConfigBuilder, Layer, MergeStrategy } ;
+use serde       :: { Serialize, Deserialize                     } ;
+use std         :: { collections::BTreeMap                      } ;
+
+mod common;
+
+
+#[ derive( Serialize, Deserialize, Debug, Clone ) ]
+//
+struct Plugins
+{
+	plugins: BTreeMap< String, String >,
+}
+
+
+
+#[ test ] fn test_append()
+{
+	let mut cfg = common::runtime_data();
+
+	// runtime_data has [ 1, 3, 5, 7, 11 ] in userset.
+	//
+	cfg.merge_runtime( "other_comp: { primes: !append [ 13 ] }" ).unwrap();
+	assert_eq!( cfg.get().other_comp.primes, vec![ 1, 3, 5, 7, 11, 13 ] );
+
+	cfg.merge_runtime( "other_comp: { primes: !append [ 17 ] }" ).unwrap();
+	assert_eq!( cfg.get().other_comp.primes, vec![ 1, 3, 5, 7, 11, 13, 17 ] );
+
+	assert_eq!( cfg.layer( Layer::RUNTIME ).unwrap().directives()[ "/other_comp/primes" ], MergeStrategy::Append );
+
+	// A plain value replaces the sequence again.
+	//
+	cfg.merge_runtime( "other_comp: { primes: [ 2 ] }" ).unwrap();
+	assert_eq!( cfg.get().other_comp.primes, vec![ 2 ] );
+
+	assert!( cfg.layer( Layer::RUNTIME ).unwrap().directives().is_empty() );
+}
+
+
+#[ test ] fn test_prepend_union()
+{
+	let mut cfg = common::runtime_data();
+
+	cfg.merge_runtime( "other_comp: { primes: !prepend [ 2 ] }" ).unwrap();
+	assert_eq!( cfg.get().other_comp.primes, vec![ 2, 1, 3, 5, 7, 11 ] );
+
+	let mut cfg = common::runtime_data();
+
+	cfg.merge_runtime( "other_comp: { primes: !union [ 11, 13 ] }" ).unwrap();
+	assert_eq!( cfg.get().other_comp.primes, vec![ 1, 3, 5, 7, 11, 13 ] );
+}
+
+
+#[ test ] fn test_replace()
+{
+	let build = |userset: &str| -> Config<Plugins>
+	{
+		ConfigBuilder::new()
+
+			.string( Layer::DEFAULT, "plugins: { spell: /usr/lib/spell.so, vim: /usr/lib/vim.so }" )
+			.string( Layer::USERSET, userset                                                      )
+
+			.build().unwrap()
+	};
+
+	assert_eq!( build( "plugins: { emacs: /usr/lib/emacs.so }" ).get().plugins.len(), 3 );
+
+	let cfg = build( "plugins: !replace { emacs: /usr/lib/emacs.so }" );
+
+	assert_eq!( cfg.get().plugins.len()      , 1                   );
+	assert_eq!( cfg.get().plugins[ "emacs" ], "/usr/lib/emacs.so" );
+}
+
+
+#[ test ] fn test_override_declared()
+{
+	// The directive wins over the strategy declared for the config.
+	//
+	let mut cfg = common::runtime_data();
+
+	cfg.set_merge_strategy( "/other_comp/primes", MergeStrategy::Append ).unwrap();
+	cfg.merge_runtime( "other_comp: { primes: !replace [ 2 ] }" ).unwrap();
+
+	assert_eq!( cfg.get().other_comp.primes, vec![ 2 ] );
+}
+
+
+#[ test ] fn test_incompatible()
+{
+	let mut cfg = common::runtime_data();
+
+	// Not a sequence in the input.
+	//
+	assert!( cfg.merge_runtime( "other_comp: { algo: !append euler }" ).is_err() );
+
+	// Not a sequence below.
+	//
+	assert!( cfg.merge_runtime( "other_comp: { algo: !append [ euler ] }" ).is_err() );
+
+	// Replace works on anything.
+	//
+	cfg.merge_runtime( "other_comp: { algo: !replace euler }" ).unwrap();
+	assert_eq!( cfg.get().other_comp.algo, "euler" );
+}
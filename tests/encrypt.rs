@@ -0,0 +1,118 @@
+use ekke_config :: { Config, ConfigBuilder, EkkeCfgError, EncryptionKey, Layer, Secret } ;
+use serde       :: { Serialize, Deserialize                                            } ;
+use std         :: { convert::TryFrom, path::Path                                      } ;
+
+
+#[ derive( Serialize, Deserialize, Debug, Clone ) ]
+//
+struct Settings
+{
+	db: DbOpts,
+}
+
+#[ derive( Serialize, Deserialize, Debug, Clone ) ]
+//
+struct DbOpts
+{
+	user    : String        ,
+	password: Secret<String>,
+	port    : u16           ,
+}
+
+
+fn root_cause( err: &failure::Error ) -> Option< &EkkeCfgError >
+{
+	err.find_root_cause().downcast_ref::< EkkeCfgError >()
+}
+
+
+
+#[ test ] fn test_key_file()
+{
+	let cfg: Config<Settings> = Config::try_from( Path::new( "data/encrypt/defaults.yml" ) ).unwrap();
+
+	assert_eq!( cfg.get().db.password.expose(), "hunter2" );
+	assert_eq!( cfg.get().db.port             , 5432      );
+
+	assert!( !format!( "{:?}", cfg ).contains( "hunter2" ) );
+}
+
+
+#[ test ] fn test_roundtrip()
+{
+	let key = EncryptionKey::generate().unwrap();
+	let ct  = key.encrypt( "s3cr3t" ).unwrap();
+
+	assert_eq!( EncryptionKey::from_base64( &key.to_base64() ).unwrap(), key );
+
+	let input = format!( "db: {{ user: admin, password: !encrypted {}, port: 5432 }}", ct );
+
+	let cfg: Config<Settings> = ConfigBuilder::new()
+
+		.string  ( Layer::DEFAULT, input                                        )
+		.resolver( ekke_config::EncryptedTag::new( key )                       )
+
+		.build().unwrap();
+
+	assert_eq!( cfg.get().db.password.expose(), "s3cr3t" );
+}
+
+
+#[ test ] fn test_literal()
+{
+	// Decrypted values are not interpolated.
+	//
+	let key = EncryptionKey::generate().unwrap();
+	let ct  = key.encrypt( "pa${x}$$ss" ).unwrap();
+
+	let input = format!( "db: {{ user: admin, password: !encrypted {}, port: 5432 }}", ct );
+
+	let cfg: Config<Settings> = ConfigBuilder::new()
+
+		.string  ( Layer::DEFAULT, input                                        )
+		.resolver( ekke_config::EncryptedTag::new( key )                       )
+
+		.build().unwrap();
+
+	assert_eq!( cfg.get().db.password.expose(), "pa${x}$$ss" );
+}
+
+
+#[ test ] fn test_missing_key()
+{
+	let input = "db: { user: admin, password: !encrypted AAAA, port: 5432 }";
+
+	let err = ConfigBuilder::new().string( Layer::DEFAULT, input ).build::<Settings>().unwrap_err();
+
+	assert!( matches!( root_cause( &err ), Some( EkkeCfgError::MissingKey ) ) );
+
+	let err = ConfigBuilder::new()
+
+		.string  ( Layer::DEFAULT, input            )
+		.key_file( "data/encrypt/no_such_key"       )
+
+		.build::<Settings>().unwrap_err();
+
+	assert!( matches!( root_cause( &err ), Some( EkkeCfgError::MissingKey ) ) );
+}
+
+
+#[ test ] fn test_tampered()
+{
+	let key = EncryptionKey::from_file( "data/encrypt/key" ).unwrap();
+	let ct  = key.encrypt( "hunter2" ).unwrap();
+
+	// Flip a character in the ciphertext.
+	//
+	let mut tampered: Vec<char> = ct.chars().collect();
+	tampered[ 40 ] = if tampered[ 40 ] == 'A' { 'B' } else { 'A' };
+	let tampered: String = tampered.into_iter().collect();
+
+	assert!( matches!( root_cause( &key.decrypt( &tampered ).unwrap_err() ), Some( EkkeCfgError::Decryption ) ) );
+
+	// The wrong key.
+	//
+	let other = EncryptionKey::generate().unwrap();
+
+	assert!( matches!( root_cause( &other.decrypt( &ct ).unwrap_err() ), Some( EkkeCfgError::Decryption ) ) );
+}
@@ -0,0 +1,198 @@
+use serde_yaml  :: { Value                    } ;
+use ekke_config :: { Change, Config, Pointer  } ;
+use std         :: { convert::TryFrom             } ;
+
+mod common;
+use common::*;
+
+
+
+#[ test ] fn test_undo_redo()
+{
+	let mut cfg = file_data();
+
+	assert!( !cfg.can_undo() );
+	assert!( !cfg.undo().unwrap() );
+
+	cfg.merge_runtime( "other_comp: { algo: gauss }" ).unwrap();
+	cfg.merge_runtime( "other_comp: { algo: newton }" ).unwrap();
+
+	assert_eq!( cfg.history_len()     , 3 );
+	assert_eq!( cfg.history_position(), 2 );
+
+	assert!( cfg.undo().unwrap() );
+	assert_eq!( cfg.get().other_comp.algo, "gauss" );
+
+	assert!( cfg.undo().unwrap() );
+	assert_eq!( cfg.get().other_comp.algo, "euler" );
+	assert!( cfg.runtime().is_none() );
+	assert!( !cfg.can_undo() );
+
+	assert!( cfg.redo().unwrap() );
+	assert!( cfg.redo().unwrap() );
+	assert!( !cfg.redo().unwrap() );
+	assert_eq!( cfg.get().other_comp.algo, "newton" );
+}
+
+
+#[ test ] fn test_new_change_drops_redo()
+{
+	let mut cfg = file_data();
+
+	cfg.merge_runtime( "other_comp: { algo: gauss }" ).unwrap();
+	cfg.undo().unwrap();
+
+	assert!( cfg.can_redo() );
+
+	cfg.merge_userset( "my_app: { log_lvl: info }" ).unwrap();
+
+	assert!( !cfg.can_redo() );
+	assert_eq!( cfg.history_len(), 2 );
+
+	cfg.undo().unwrap();
+	assert_eq!( cfg.get().my_app.log_lvl, "warn" );
+}
+
+
+#[ test ] fn test_failed_change_not_recorded()
+{
+	let mut cfg = file_data();
+
+	assert!( cfg.merge_runtime( "other_comp: { primes: not a list }" ).is_err() );
+	assert_eq!( cfg.history_len(), 0 );
+}
+
+
+#[ test ] fn test_limit()
+{
+	let mut cfg = file_data();
+
+	cfg.set_history_limit( 2 );
+
+	for algo in &[ "a", "b", "c", "d" ]
+	{
+		cfg.merge_runtime( format!( "other_comp: {{ algo: {} }}", algo ).as_str() ).unwrap();
+	}
+
+	assert_eq!( cfg.history_len(), 3 );
+
+	cfg.undo().unwrap();
+	cfg.undo().unwrap();
+
+	assert!( !cfg.can_undo() );
+	assert_eq!( cfg.get().other_comp.algo, "b" );
+}
+
+
+#[ test ] fn test_limit_after_undo()
+{
+	let mut cfg = file_data();
+
+	for algo in &[ "a", "b", "c", "d", "e", "f", "g" ]
+	{
+		cfg.merge_runtime( format!( "other_comp: {{ algo: {} }}", algo ).as_str() ).unwrap();
+	}
+
+	for _ in 0..5 { cfg.undo().unwrap(); }
+
+	assert_eq!( cfg.history_position(), 2 );
+
+	// The undone states are dropped first, the current state is kept.
+	//
+	cfg.set_history_limit( 3 );
+
+	assert_eq!( cfg.history_len()     , 4 );
+	assert_eq!( cfg.history_position(), 0 );
+	assert_eq!( cfg.get().other_comp.algo, "b" );
+
+	assert!( cfg.redo().unwrap() );
+	assert_eq!( cfg.get().other_comp.algo, "c" );
+
+	assert!( cfg.undo().unwrap() );
+	assert_eq!( cfg.get().other_comp.algo, "b" );
+	assert!( !cfg.can_undo() );
+}
+
+
+#[ test ] fn test_changes()
+{
+	let mut cfg = file_data();
+
+	cfg.merge_runtime( "other_comp: { algo: gauss }" ).unwrap();
+	cfg.merge_userset( "my_app: { log_lvl: info }"   ).unwrap();
+
+	assert_eq!
+	(
+		cfg.changes( 0, 2 ).unwrap(),
+
+		vec!
+		[
+			Change{ layer: "userset".into(), pointer: "/my_app/log_lvl".into()  , old: Some( "warn".into() ), new: Some( "info".into()  ) },
+			Change{ layer: "runtime".into(), pointer: "/other_comp/algo".into() , old: None                 , new: Some( "gauss".into() ) },
+		]
+	);
+
+	assert!( cfg.changes( 0, 3 ).is_none() );
+}
+
+
+#[ test ] fn test_changes_redacted()
+{
+	let mut cfg = file_data();
+
+	cfg.add_sensitive( "/my_app/db_path" );
+	cfg.merge_runtime( "my_app: { db_path: secret.sqlite }" ).unwrap();
+
+	let changes = cfg.changes( 0, 1 ).unwrap();
+
+	assert_eq!( changes[0].pointer, "/my_app/db_path" );
+	assert_eq!( changes[0].new    , Some( Value::from( "***" ) ) );
+}
+
+
+#[ test ] fn test_revert_userset()
+{
+	let mut cfg = file_data();
+
+	cfg.merge_userset( "my_app: { log_lvl: info }" ).unwrap();
+	cfg.revert_userset().unwrap();
+
+	assert_eq!( cfg.get().my_app.log_lvl, "warn" );
+	assert_eq!( cfg.userset().unwrap().jptr( "/other_comp/algo" ).unwrap(), "euler" );
+
+	// Reverting can be undone.
+	//
+	cfg.undo().unwrap();
+	assert_eq!( cfg.get().my_app.log_lvl, "info" );
+}
+
+
+#[ test ] fn test_revert_userset_without_file()
+{
+	let mut cfg: Config<Settings> = Config::try_from( "default: { my_app: { db_path: a, log_lvl: debug }, other_comp: { primes: [], algo: b } }" ).unwrap();
+
+	cfg.merge_userset( "my_app: { log_lvl: info }" ).unwrap();
+	cfg.revert_userset().unwrap();
+
+	assert!( cfg.userset().is_none() );
+	assert_eq!( cfg.get().my_app.log_lvl, "debug" );
+}
+
+
+#[ test ] fn test_transaction()
+{
+	let mut cfg = file_data();
+
+	let mut tx = cfg.transaction();
+
+	tx.merge_runtime( "other_comp: { algo: gauss }" ).unwrap();
+	tx.merge_userset( "my_app: { log_lvl: info }"   ).unwrap();
+	tx.commit().unwrap();
+
+	assert_eq!( cfg.history_len(), 2 );
+
+	cfg.undo().unwrap();
+
+	assert_eq!( cfg.get().my_app.log_lvl   , "warn"  );
+	assert_eq!( cfg.get().other_comp.algo  , "euler" );
+}
@@ -0,0 +1,199 @@
+use ekke_config :: { Config, ConfigBuilder, EkkeCfgError, Layer } ;
+use serde_yaml  :: { Value                                       } ;
+use std         :: { convert::TryFrom, path::{ Path, PathBuf }   } ;
+
+mod common;
+use common::*;
+
+
+fn data( file: &str ) -> PathBuf
+{
+	PathBuf::from( env!( "CARGO_MANIFEST_DIR" ) ).join( "data/include" ).join( file )
+}
+
+
+
+#[ test ] fn test_include()
+{
+	let cfg: Config<Settings> = Config::try_from( &data( "defaults.yml" ) ).unwrap();
+
+	assert_eq!( cfg.get().my_app.db_path   , "data/db.sqlite"      );
+	assert_eq!( cfg.get().my_app.log_lvl   , "warn"                );
+	assert_eq!( cfg.get().other_comp.primes, vec![ 1, 3, 5, 7, 11 ] );
+	assert_eq!( cfg.get().other_comp.algo  , "gauss"               );
+
+	// The glob pattern merges the files in alphabetical order.
+	//
+	let default = cfg.default();
+	assert_eq!( default[ "other_comp" ][ "algo" ], "fournier" );
+}
+
+
+#[ test ] fn test_provenance()
+{
+	let cfg: Config<Settings> = Config::try_from( &data( "defaults.yml" ) ).unwrap();
+
+	assert_eq!
+	(
+		cfg.layer( Layer::DEFAULT ).unwrap().includes(),
+
+		&[ data( "my_app.yml" ), data( "components/10-primes.yml" ), data( "components/20-algo.yml" ) ]
+	);
+
+	assert_eq!
+	(
+		cfg.layer( Layer::USERSET ).unwrap().includes(),
+
+		&[ data( "user/other_comp.yml" ), data( "user/primes.yml" ) ]
+	);
+
+	let files = cfg.files();
+
+	assert_eq!( files.len(), 7                       );
+	assert_eq!( files[0]   , data( "defaults.yml" ) );
+	assert!   ( files.contains( &data( "userset.yml" ) ) );
+}
+
+
+#[ test ] fn test_builder()
+{
+	// Relative includes in a file source are relative to the file.
+	//
+	let cfg: Config<Settings> = ConfigBuilder::new()
+
+		.string( Layer::DEFAULT, "my_app: { db_path: data/db.sqlite, log_lvl: debug }" )
+		.file  ( Layer::USERSET, data( "userset.yml" )                                  )
+
+		.build().unwrap();
+
+	assert_eq!( cfg.get().other_comp.algo  , "gauss"                );
+	assert_eq!( cfg.get().other_comp.primes, vec![ 1, 3, 5, 7, 11 ] );
+}
+
+
+#[ test ] fn test_cwd()
+{
+	// Merged strings resolve relative includes against the working directory, which for tests is the crate root.
+	//
+	let mut cfg = common::file_data();
+
+	cfg.merge_runtime( "other_comp: !include data/include/user/other_comp.yml" ).unwrap();
+
+	assert_eq!( cfg.get().other_comp.algo, "gauss" );
+
+	assert_eq!
+	(
+		cfg.layer( Layer::RUNTIME ).unwrap().includes(),
+
+		&[ Path::new( "data/include/user/other_comp.yml" ), Path::new( "data/include/user/primes.yml" ) ]
+	);
+}
+
+
+#[ test ] fn test_cycle()
+{
+	let err = ConfigBuilder::new().file( Layer::DEFAULT, data( "cycle/a.yml" ) ).build::<Settings>().unwrap_err();
+
+	match err.find_root_cause().downcast_ref::< EkkeCfgError >()
+	{
+		Some( EkkeCfgError::IncludeCycle( cycle ) ) => assert!( cycle.ends_with( "a.yml" ) ),
+		_                                           => panic!( "expected an include cycle, got: {}", err ),
+	}
+}
+
+
+#[ test ] fn test_missing()
+{
+	assert!( common::file_data().merge_runtime( "my_app: !include data/include/nope.yml" ).is_err() );
+}
+
+
+#[ test ] fn test_tags()
+{
+	// Tags that nothing handles are ignored, core tags apply.
+	//
+	let cfg: Config<Settings> = Config::try_from
+	(
+"
+default:
+  my_app: !foo
+    db_path: !!str 12
+    log_lvl: &lvl debug
+
+  other_comp:
+    primes: !bar [ 1, 3 ]
+    algo  : *lvl
+"
+	).unwrap();
+
+	assert_eq!( cfg.get().my_app.db_path   , "12"         );
+	assert_eq!( cfg.get().other_comp.primes, vec![ 1, 3 ] );
+	assert_eq!( cfg.get().other_comp.algo  , "debug"      );
+}
+
+
+#[ test ] fn test_core_tags()
+{
+	// Core tags set the type of quoted values as well, like for serde_yaml.
+	//
+	let cfg: Config<Settings> = Config::try_from
+	(
+"
+default:
+  my_app:
+    db_path: !!str 12
+    log_lvl: debug
+
+  other_comp:
+    primes: [ !!int '5', !!int \"7\" ]
+    algo  : euler
+"
+	).unwrap();
+
+	assert_eq!( cfg.get().my_app.db_path   , "12"         );
+	assert_eq!( cfg.get().other_comp.primes, vec![ 5, 7 ] );
+
+	assert!( Config::<Settings>::try_from( "default: { my_app: { db_path: !!int five } }" ).is_err() );
+}
+
+
+#[ test ] fn test_big_integers()
+{
+	// Integers that don't fit in 64 bits are an error, like for serde_yaml, rather than a float.
+	//
+	let parse = |input: &str| ConfigBuilder::new().string( Layer::DEFAULT, input ).build::<Value>();
+
+	assert!( parse( "a: 18446744073709551616" ).is_err() );
+	assert!( parse( "a: -9223372036854775809" ).is_err() );
+
+	assert_eq!( parse( "a: 18446744073709551615" ).unwrap().get()[ "a" ], Value::from( u64::MAX ) );
+	assert_eq!( parse( "a: 1e30"                 ).unwrap().get()[ "a" ], Value::from( 1e30     ) );
+}
+
+
+#[ test ] fn test_alias_tags()
+{
+	// An alias gets the value of its anchor, but not its tags, so only the anchor is locked.
+	//
+	let mut cfg: Config<Settings> = Config::try_from
+	(
+"
+default:
+  my_app:
+    db_path: data/db.sqlite
+    log_lvl: !locked &lvl debug
+
+  other_comp:
+    primes: [ 1 ]
+    algo  : *lvl
+"
+	).unwrap();
+
+	assert_eq!( cfg.get().other_comp.algo, "debug" );
+	assert_eq!( cfg.locks().iter().map( |( ptr, _ )| *ptr ).collect::<Vec<_>>(), vec![ "/my_app/log_lvl" ] );
+
+	cfg.merge_runtime( "other_comp: { algo: gauss }" ).unwrap();
+
+	assert_eq!( cfg.get().other_comp.algo, "gauss" );
+	assert!( cfg.merge_runtime( "my_app: { log_lvl: info }" ).is_err() );
+}
@@ -0,0 +1,104 @@
+use ekke_config :: { Config, EkkeCfgError } ;
+use std         :: { convert::TryFrom, env } ;
+
+mod common;
+use common::*;
+
+
+const DEFAULT: &str =
+"
+default:
+
+  data_dir: /var/lib/ekke
+
+  my_app:
+    db_path: ${data_dir}/db.sqlite
+    log_lvl: ${/other_comp/algo}
+
+  other_comp:
+    primes: ${/my_app/primes}
+    algo  : fournier
+
+  # Not part of Settings, so these are ignored when deserializing.
+  #
+  extra:
+    home   : ${env:EKKE_INTERPOLATE_TEST}
+    literal: costs $$${price}
+";
+
+
+fn cfg() -> Config<Settings>
+{
+	env::set_var( "EKKE_INTERPOLATE_TEST", "/home/ekke" );
+
+	let input = DEFAULT.replace( "    primes: ${/my_app/primes}", "    primes: [ 1, 2 ]" );
+
+	Config::try_from( input.as_str() ).unwrap()
+}
+
+
+fn pointer( input: &str ) -> Option< String >
+{
+	let err = Config::<Settings>::try_from( input ).unwrap_err();
+
+	err.iter_chain().find_map( |e| match e.downcast_ref::<EkkeCfgError>()
+	{
+		Some( EkkeCfgError::Interpolation{ pointer, .. } ) => Some( pointer.clone() ),
+		_                                                  => None                   ,
+	})
+}
+
+
+
+#[ test ] fn test_references()
+{
+	let cfg = cfg();
+
+	assert_eq!( cfg.get().my_app.db_path, "/var/lib/ekke/db.sqlite" );
+	assert_eq!( cfg.get().my_app.log_lvl, "fournier"                );
+
+	// The layers keep the references.
+	//
+	assert_eq!( cfg.default()["my_app"]["db_path"], "${data_dir}/db.sqlite" );
+}
+
+
+#[ test ] fn test_overrides()
+{
+	let mut cfg = cfg();
+
+	cfg.merge_userset( "data_dir: /srv/ekke"         ).unwrap();
+	cfg.merge_runtime( "other_comp: { algo: euler }" ).unwrap();
+
+	assert_eq!( cfg.get().my_app.db_path, "/srv/ekke/db.sqlite" );
+	assert_eq!( cfg.get().my_app.log_lvl, "euler"               );
+}
+
+
+#[ test ] fn test_typed()
+{
+	let input = DEFAULT.replace( "    primes: ${/my_app/primes}", "    primes: ${/extra/primes}" ) + "    primes: [ 2, 3, 5 ]\n";
+	let cfg   = Config::<Settings>::try_from( input.as_str() ).unwrap();
+
+	assert_eq!( cfg.get().other_comp.primes, vec![ 2, 3, 5 ] );
+}
+
+
+#[ test ] fn test_errors()
+{
+	// Missing key
+	//
+	assert_eq!( pointer( DEFAULT ), Some( "/other_comp/primes".to_string() ) );
+
+	// Cycle
+	//
+	let input = DEFAULT.replace( "    algo  : fournier", "    algo  : ${/my_app/log_lvl}" );
+
+	assert!( pointer( &input ).is_some() );
+
+	// Unset environment variable
+	//
+	let input = DEFAULT.replace( "EKKE_INTERPOLATE_TEST", "EKKE_INTERPOLATE_UNSET" ).replace( "    primes: ${/my_app/primes}", "    primes: []" );
+
+	assert_eq!( pointer( &input ), Some( "/extra/home".to_string() ) );
+}
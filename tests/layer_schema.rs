@@ -0,0 +1,202 @@
+#![ cfg( feature = "schema" ) ]
+
+use ekke_config :: { Config, ConfigBuilder, EkkeCfgError, Layer, Violation } ;
+use serde_json  :: { json, Value as JsonValue                             } ;
+use std         :: { convert::TryFrom, path::Path                          } ;
+
+mod common;
+use common::*;
+
+
+fn schema() -> JsonValue
+{
+	json!
+	({
+		"type": "object",
+		"properties":
+		{
+			"my_app":
+			{
+				"type"      : "object",
+				"required"  : [ "db_path", "log_lvl" ],
+				"properties": { "log_lvl": { "enum": [ "error", "warn", "info", "debug" ] } },
+			},
+
+			"other_comp": { "properties": { "primes": { "minItems": 1 }, "mode": { "enum": [ "fast", "slow" ] } } },
+		}
+	})
+}
+
+
+fn violations( err: failure::Error ) -> Vec< Violation >
+{
+	match err.find_root_cause().downcast_ref::< EkkeCfgError >()
+	{
+		Some( EkkeCfgError::Invalid( violations ) ) => violations.to_vec(),
+		_                                           => panic!( "wrong error: {}", err ),
+	}
+}
+
+
+
+#[ test ] fn test_rejected()
+{
+	let mut cfg = file_data();
+
+	cfg.set_schema( Some( &schema() ) ).unwrap();
+
+	cfg.merge_runtime( "my_app: { log_lvl: info }" ).unwrap();
+
+	let err = cfg.merge_runtime( "{ my_app: { log_lvl: loud }, other_comp: { primes: [] } }" ).unwrap_err();
+	let all = violations( err );
+
+	assert_eq!( all.len(), 2 );
+
+	assert_eq!( all[0].pointer, "/my_app/log_lvl"           );
+	assert_eq!( all[0].layer  , Some( "runtime".to_string() ) );
+	assert_eq!( all[0].origin , None                        );
+	assert_eq!( all[1].pointer, "/other_comp/primes"        );
+
+	// The config is left unchanged.
+	//
+	assert_eq!( cfg.get().my_app.log_lvl, "info" );
+}
+
+
+#[ test ] fn test_partial_layers()
+{
+	let mut cfg = file_data();
+
+	cfg.set_schema( Some( &schema() ) ).unwrap();
+
+	// Layers above the defaults don't need the required properties and can remove values with null.
+	//
+	cfg.merge_runtime( "my_app: { log_lvl: warn }"         ).unwrap();
+	cfg.merge_layer  ( "extra", "other_comp: { mode: ~ }" ).unwrap();
+
+	// The defaults do.
+	//
+	let err = cfg.remove_key( Layer::DEFAULT, "/my_app/db_path" ).unwrap_err();
+	let all = violations( err );
+
+	assert_eq!( all[0].pointer, "/my_app"                  );
+	assert_eq!( all[0].layer  , Some( "default".to_string() ) );
+}
+
+
+#[ test ] fn test_defaults_file()
+{
+	let err = Config::<Settings>::try_from( Path::new( "data/schema/defaults.yml" ) ).unwrap_err();
+	let all = violations( err );
+
+	let found: Vec<( &str, Option< &str > )> = all.iter().map( |v| ( v.pointer.as_str(), v.origin.as_deref() ) ).collect();
+
+	// All violations of the userset file are reported, not just the first one.
+	//
+	assert_eq!( found.len(), 2 );
+	assert!( found.contains( &( "/my_app"        , Some( "data/schema/userset.yml" ) ) ) );
+	assert!( found.contains( &( "/my_app/log_lvl", Some( "data/schema/userset.yml" ) ) ) );
+
+	assert!( all[0].to_string().ends_with( "(layer userset, from data/schema/userset.yml)" ) );
+}
+
+
+#[ test ] fn test_builder()
+{
+	let defaults = "{ my_app: { db_path: a, log_lvl: info }, other_comp: { primes: [ 2 ], algo: b } }";
+
+	let cfg: Result< Config<Settings>, _ > = ConfigBuilder::new()
+
+		.string     ( Layer::DEFAULT, defaults                  )
+		.string     ( Layer::USERSET, "my_app: { colour: red }" )
+		.schema_file( "data/schema/schema.json"                 )
+
+		.build();
+
+	let err = violations( cfg.unwrap_err() );
+
+	assert_eq!( err[0].pointer, "/my_app"                  );
+	assert_eq!( err[0].layer  , Some( "userset".to_string() ) );
+
+	let cfg: Result< Config<Settings>, _ > = ConfigBuilder::new()
+
+		.string     ( Layer::DEFAULT, defaults  )
+		.schema_file( "data/schema/schema.json" )
+
+		.build();
+
+	assert!( cfg.is_ok() );
+}
+
+
+#[ test ] fn test_sensitive()
+{
+	let mut cfg = file_data();
+
+	cfg.add_sensitive( "/my_app/log_lvl" );
+	cfg.set_schema( Some( &schema() ) ).unwrap();
+
+	let err = cfg.merge_runtime( "my_app: { log_lvl: hunter2 }" ).unwrap_err();
+
+	assert!( !err.to_string().contains( "hunter2" ) );
+
+	// A violation of the object that contains a sensitive value shows the object.
+	//
+	cfg.add_sensitive( "/other_comp/algo" );
+	cfg.set_schema( Some( &json!({ "properties": { "other_comp": { "maxProperties": 2 } } }) ) ).unwrap();
+
+	let err = cfg.merge_runtime( "other_comp: { algo: hunter2, mode: fast, extra: 1 }" ).unwrap_err();
+
+	assert!( !err.to_string().contains( "hunter2" ) );
+
+	assert_eq!( violations( err )[0].pointer, "/other_comp" );
+}
+
+
+#[ test ] fn test_invalid_schema()
+{
+	let mut cfg = file_data();
+
+	let err = cfg.set_schema( Some( &json!({ "type": 5 }) ) ).unwrap_err();
+
+	assert!( matches!( err.find_root_cause().downcast_ref(), Some( EkkeCfgError::ConfigParse ) ) );
+
+	// Without a schema, anything that deserializes goes.
+	//
+	cfg.set_schema( None ).unwrap();
+	cfg.merge_runtime( "my_app: { log_lvl: loud }" ).unwrap();
+}
+
+
+#[ test ] fn test_partial_collections()
+{
+	// Elements of sequences and entries of maps don't merge with the layers below, so they must be complete.
+	//
+	let mut cfg: Config<serde_yaml::Value> = ConfigBuilder::new()
+
+		.string( Layer::DEFAULT, "{ plugins: [ { name: a, enabled: true } ], servers: { main: { host: localhost, port: 80 } } }" )
+		.build().unwrap();
+
+	let plugin = json!({ "type": "object", "required": [ "name", "enabled" ] });
+	let server = json!({ "type": "object", "required": [ "host", "port" ] });
+
+	cfg.set_schema( Some( &json!
+	({
+		"type"      : "object",
+		"required"  : [ "plugins", "servers" ],
+		"properties":
+		{
+			"plugins": { "type": "array" , "items"               : plugin },
+			"servers": { "type": "object", "additionalProperties": server },
+		}
+
+	}))).unwrap();
+
+	cfg.merge_runtime( "servers: { backup: { host: localhost, port: 8081 } }" ).unwrap();
+
+	let err = cfg.merge_runtime( "plugins: [ { name: b } ]" ).unwrap_err();
+	assert_eq!( violations( err )[0].pointer, "/plugins/0" );
+
+	let err = cfg.merge_runtime( "servers: { spare: { port: 8082 } }" ).unwrap_err();
+	assert_eq!( violations( err )[0].pointer, "/servers/spare" );
+}
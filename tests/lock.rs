@@ -0,0 +1,131 @@
+use ekke_config :: { Config, ConfigBuilder, EkkeCfgError, Layer } ;
+use std         :: { convert::TryFrom, path::PathBuf             } ;
+
+mod common;
+use common::*;
+
+
+fn data( file: &str ) -> PathBuf
+{
+	PathBuf::from( env!( "CARGO_MANIFEST_DIR" ) ).join( "data/lock" ).join( file )
+}
+
+
+
+#[ test ] fn test_locked_file()
+{
+	// The userset file can not change the locked db_path, but it can change the log level.
+	//
+	let cfg: Config<Settings> = Config::try_from( &data( "defaults.yml" ) ).unwrap();
+
+	assert_eq!( cfg.get().my_app.db_path, "data/db.sqlite" );
+	assert_eq!( cfg.get().my_app.log_lvl, "warn"           );
+
+	assert_eq!( cfg.warnings().len(), 1 );
+	assert!   ( cfg.warnings()[0].contains( "/my_app/db_path" ) );
+
+	// The layer keeps what the file says.
+	//
+	assert_eq!( cfg.userset().unwrap()[ "my_app" ][ "db_path" ], "/tmp/db.sqlite" );
+}
+
+
+#[ test ] fn test_locks()
+{
+	let cfg: Config<Settings> = Config::try_from( &data( "defaults.yml" ) ).unwrap();
+
+	let locks: Vec<( &str, &str )> = cfg.locks().into_iter().map( |( ptr, layer )| ( ptr, layer.name() ) ).collect();
+
+	assert_eq!( locks, vec![ ( "/my_app/db_path", Layer::DEFAULT ), ( "/other_comp", Layer::DEFAULT ) ] );
+
+	assert_eq!( cfg.locks()[0].1.origin(), Some( data( "defaults.yml" ).to_str().unwrap() ) );
+}
+
+
+#[ test ] fn test_merge_locked()
+{
+	let mut cfg: Config<Settings> = Config::try_from( &data( "defaults.yml" ) ).unwrap();
+
+	for input in &[ "my_app: { db_path: /tmp/db.sqlite }", "other_comp: { algo: euler }", "other_comp: ~" ]
+	{
+		let err = cfg.merge_runtime( input ).unwrap_err();
+
+		match err.downcast_ref::<EkkeCfgError>()
+		{
+			Some( EkkeCfgError::Locked{ layer, .. } ) => assert_eq!( layer, Layer::DEFAULT ),
+			_                                         => panic!( "unexpected error: {}", err ),
+		}
+	}
+
+	// Nothing was merged.
+	//
+	assert!( cfg.runtime().is_none() );
+
+	// Unlocked values can still change.
+	//
+	cfg.merge_runtime( "my_app: { log_lvl: info }" ).unwrap();
+
+	assert_eq!( cfg.get().my_app.log_lvl, "info"           );
+	assert_eq!( cfg.get().my_app.db_path, "data/db.sqlite" );
+}
+
+
+#[ test ] fn test_same_layer()
+{
+	// A layer can change its own locked values, and locks only apply to the layers above.
+	//
+	let mut cfg: Config<Settings> = ConfigBuilder::new()
+
+		.string( Layer::DEFAULT, "{ my_app: { db_path: data/db.sqlite, log_lvl: debug }, other_comp: { primes: [ 1 ], algo: euler } }" )
+		.string( "system"      , "my_app: { db_path: !locked /var/lib/db.sqlite }"                                                   )
+
+		.build().unwrap();
+
+	assert_eq!( cfg.get().my_app.db_path, "/var/lib/db.sqlite" );
+
+	cfg.merge_layer( "system", "my_app: { db_path: /srv/db.sqlite }" ).unwrap();
+
+	assert_eq!( cfg.get().my_app.db_path, "/srv/db.sqlite" );
+
+	assert!( cfg.merge_userset( "my_app: { db_path: /tmp/db.sqlite }" ).is_err() );
+}
+
+
+#[ test ] fn test_replace_locked()
+{
+	// Replacing the parent of a locked value overrides the lock as well.
+	//
+	let mut cfg: Config<Settings> = ConfigBuilder::new()
+
+		.string( Layer::DEFAULT, "{ my_app: { db_path: data/db.sqlite, log_lvl: debug }, other_comp: { primes: [ 1 ], algo: euler } }" )
+		.string( "system"      , "my_app: { db_path: !locked /var/lib/db.sqlite }"                                                   )
+		.string( "user"        , "my_app: !replace { log_lvl: warn }"                                                                )
+
+		.build().unwrap();
+
+	// A layer that was loaded keeps the locked value and gets a warning.
+	//
+	assert_eq!( cfg.get().my_app.db_path, "/var/lib/db.sqlite" );
+	assert_eq!( cfg.get().my_app.log_lvl, "warn"               );
+
+	assert_eq!( cfg.warnings().len(), 1 );
+	assert!   ( cfg.warnings()[0].contains( "/my_app/db_path" ) );
+
+	// Merging fails.
+	//
+	let err = cfg.merge_runtime( "my_app: !replace { log_lvl: info }" ).unwrap_err();
+
+	match err.downcast_ref::<EkkeCfgError>()
+	{
+		Some( EkkeCfgError::Locked{ pointer, layer } ) =>
+		{
+			assert_eq!( pointer, "/my_app/db_path" );
+			assert_eq!( layer  , "system"          );
+		}
+
+		_ => panic!( "unexpected error: {}", err ),
+	}
+
+	assert!   ( cfg.runtime().is_none() );
+	assert_eq!( cfg.get().my_app.db_path, "/var/lib/db.sqlite" );
+}
@@ -0,0 +1,124 @@
+use ekke_config :: { Config, EkkeCfgError, Pointer } ;
+use std         :: { convert::TryFrom               } ;
+
+mod common;
+use common::*;
+
+
+fn patch_index( err: failure::Error ) -> usize
+{
+	match err.downcast_ref::<EkkeCfgError>()
+	{
+		Some( EkkeCfgError::Patch{ index, .. } ) => *index,
+		_                                        => panic!( "unexpected error: {}", err ),
+	}
+}
+
+
+
+#[ test ] fn test_patch()
+{
+	let mut cfg = common::runtime_data();
+
+	cfg.patch_userset( r#"
+    [
+        { "op": "test"   , "path": "/other_comp/algo"    , "value": "euler"     },
+        { "op": "replace", "path": "/other_comp/algo"    , "value": "gauss"     },
+        { "op": "add"    , "path": "/other_comp/primes/-", "value": 13          },
+        { "op": "remove" , "path": "/other_comp/primes/0"                       },
+        { "op": "copy"   , "from": "/my_app/log_lvl"     , "path" : "/log_copy" },
+        { "op": "move"   , "from": "/log_copy"           , "path" : "/log_move" }
+    ]"# ).unwrap();
+
+	assert_eq!( cfg.get().other_comp.algo  , "gauss"                    );
+	assert_eq!( cfg.get().other_comp.primes, vec![ 3, 5, 7, 11, 13 ] );
+
+	let userset = cfg.userset().unwrap();
+
+	assert!   ( userset.jptr( "/log_copy" ).is_none()         );
+	assert_eq!( userset.jptr( "/log_move" ).unwrap(), "warn" );
+}
+
+
+#[ test ] fn test_patch_new_layer()
+{
+	let mut cfg = common::file_data();
+
+	cfg.patch_runtime( "[ { op: add, path: /my_app, value: { log_lvl: trace } } ]" ).unwrap();
+
+	assert_eq!( cfg.get().my_app.log_lvl, "trace" );
+}
+
+
+#[ test ] fn test_patch_rollback()
+{
+	let mut cfg = common::runtime_data();
+	let     old = cfg.clone();
+
+	// The second operation fails, so the first one is undone.
+	//
+	let err = cfg.patch_userset( r#"
+    [
+        { "op": "replace", "path": "/other_comp/algo", "value": "gauss" },
+        { "op": "test"   , "path": "/other_comp/algo", "value": "euler" }
+    ]"# ).unwrap_err();
+
+	assert_eq!( patch_index( err ), 1 );
+	assert_eq!( cfg.get().other_comp.algo, "euler" );
+
+	// The result doesn't deserialize.
+	//
+	assert!( cfg.patch_userset( r#"[ { "op": "replace", "path": "/other_comp/primes", "value": "many" } ]"# ).is_err() );
+	assert!( cfg.patch_userset( r#"[ { "op": "remove", "path": "/nothing" } ]"#                            ).is_err() );
+
+	assert_eq!( cfg.userset(), old.userset()                       );
+	assert_eq!( cfg.get().other_comp.primes, vec![ 1, 3, 5, 7, 11 ] );
+}
+
+
+#[ test ] fn test_merge_patch()
+{
+	let mut cfg = common::runtime_data();
+
+	cfg.merge_patch_userset( r#"{ "my_app": { "log_lvl": null }, "other_comp": { "algo": "gauss", "primes": [ 2 ] } }"# ).unwrap();
+
+	// Removing the key from userset brings back the default.
+	//
+	assert_eq!( cfg.get().my_app.log_lvl    , "info"    );
+	assert_eq!( cfg.get().other_comp.algo   , "gauss"   );
+	assert_eq!( cfg.get().other_comp.primes , vec![ 2 ] );
+
+	assert!( cfg.userset().unwrap().jptr( "/my_app/log_lvl" ).is_none() );
+
+	// Not valid for the settings.
+	//
+	assert!( cfg.merge_patch_runtime( "other_comp: { primes: many }" ).is_err() );
+	assert_eq!( cfg.get().other_comp.primes, vec![ 2 ] );
+}
+
+
+#[ test ] fn test_patch_locked()
+{
+	let mut cfg: Config<Settings> = Config::try_from( "default: { my_app: { db_path: !locked data/db.sqlite, log_lvl: debug }, other_comp: { primes: [ 1 ], algo: euler } }" ).unwrap();
+
+	assert!( cfg.patch_runtime      ( "[ { op: add, path: /my_app, value: { db_path: /tmp } } ]" ).is_err() );
+	assert!( cfg.merge_patch_runtime( "my_app: { db_path: /tmp }"                                ).is_err() );
+
+	assert!( cfg.runtime().is_none() );
+}
+
+
+#[ test ] fn test_patch_tags()
+{
+	// Tags only work when a layer is made from yaml, so patches refuse them.
+	//
+	let mut cfg = common::runtime_data();
+
+	assert!( cfg.patch_runtime      ( "[ { op: add, path: /my_app/db_path, value: !file /etc/passwd } ]" ).is_err() );
+	assert!( cfg.merge_patch_runtime( "my_app: { db_path: !locked /tmp/db.sqlite }"                      ).is_err() );
+
+	let before = cfg.get().clone();
+
+	assert!( cfg.merge_patch_userset( "other_comp: !replace { algo: gauss }" ).is_err() );
+	assert_eq!( cfg.get().other_comp.algo, before.other_comp.algo );
+}
@@ -0,0 +1,178 @@
+#![ cfg( unix ) ]
+
+use ekke_config :: { Config, ConfigBuilder, EkkeCfgError, FilePolicy, Layer                  } ;
+use serde_yaml  :: { Value                                                                    } ;
+use std         :: { env, fs, os::unix::fs::PermissionsExt, path::PathBuf, sync::atomic::{ AtomicUsize, Ordering } } ;
+
+
+static WARNINGS: AtomicUsize = AtomicUsize::new( 0 );
+
+
+// A temporary directory for a test, removed when dropped. Its permissions are put back first, in case
+// the test changed them.
+//
+struct TempDir( PathBuf );
+
+
+impl TempDir
+{
+	fn new( test: &str ) -> Self
+	{
+		let dir = env::temp_dir().join( format!( "ekke_config_policy_{}_{}", test, std::process::id() ) );
+		fs::create_dir_all( &dir ).unwrap();
+
+		Self( dir )
+	}
+
+
+	// Write a file with the given mode in this directory.
+	//
+	fn file( &self, name: &str, content: &str, mode: u32 ) -> PathBuf
+	{
+		let path = self.0.join( name );
+
+		fs::write( &path, content ).unwrap();
+		fs::set_permissions( &path, fs::Permissions::from_mode( mode ) ).unwrap();
+
+		path
+	}
+}
+
+
+impl Drop for TempDir
+{
+	fn drop( &mut self )
+	{
+		let _ = fs::set_permissions( &self.0, fs::Permissions::from_mode( 0o755 ) );
+		let _ = fs::remove_dir_all( &self.0 );
+	}
+}
+
+
+fn violation( result: Result< Config<Value>, failure::Error > ) -> bool
+{
+	match result
+	{
+		Err( err ) => matches!( err.find_root_cause().downcast_ref(), Some( EkkeCfgError::FilePolicy{ .. } ) ),
+		Ok ( _   ) => false,
+	}
+}
+
+
+
+#[ test ] fn test_writable()
+{
+	let tmp = TempDir::new( "writable" );
+
+	let world = tmp.file( "world.yml", "a: 1", 0o666 );
+	let group = tmp.file( "group.yml", "a: 1", 0o664 );
+	let fine  = tmp.file( "fine.yml" , "a: 1", 0o644 );
+
+	let build = |path: &PathBuf, policy: FilePolicy|
+
+		ConfigBuilder::new().file( Layer::DEFAULT, path ).file_policy( policy ).build()
+	;
+
+	assert!(  violation( build( &world, FilePolicy::new()                        ) ) );
+	assert!(  violation( build( &group, FilePolicy::new()                        ) ) );
+	assert!( !violation( build( &group, FilePolicy::new().group_writable( true ) ) ) );
+	assert!( !violation( build( &fine , FilePolicy::new()                        ) ) );
+
+	// Without a policy, anything goes.
+	//
+	assert!( ConfigBuilder::new().file( Layer::DEFAULT, &world ).build::<Value>().is_ok() );
+}
+
+
+#[ test ] fn test_directory()
+{
+	let tmp = TempDir::new( "directory" );
+
+	// Whoever can write to the directory can replace the file, unless the sticky bit is set.
+	//
+	let path = tmp.file( "config.yml", "a: 1", 0o644 );
+	let dir  = path.parent().unwrap().to_path_buf();
+
+	let build = || ConfigBuilder::new().file( Layer::DEFAULT, &path ).file_policy( FilePolicy::new() ).build();
+
+	fs::set_permissions( &dir, fs::Permissions::from_mode( 0o777 ) ).unwrap();
+	assert!( violation( build() ) );
+
+	fs::set_permissions( &dir, fs::Permissions::from_mode( 0o1777 ) ).unwrap();
+	assert!( !violation( build() ) );
+
+	fs::set_permissions( &dir, fs::Permissions::from_mode( 0o755 ) ).unwrap();
+	assert!( !violation( build() ) );
+
+	// A symlink is checked for the file it points to.
+	//
+	let world = tmp.file( "world.yml", "a: 1", 0o666 );
+	let link  = dir.join( "link.yml" );
+
+	let _ = fs::remove_file( &link );
+	std::os::unix::fs::symlink( &world, &link ).unwrap();
+
+	assert!( violation( ConfigBuilder::new().file( Layer::DEFAULT, &link ).file_policy( FilePolicy::new() ).build() ) );
+}
+
+
+#[ test ] fn test_owner()
+{
+	let tmp = TempDir::new( "owner" );
+
+	let path = tmp.file( "config.yml", "a: 1", 0o644 );
+
+	let result = ConfigBuilder::new()
+
+		.file       ( Layer::DEFAULT, &path                                  )
+		.file_policy( FilePolicy::new().owners( Some( vec![ 12345678 ] ) )   )
+		.build()
+	;
+
+	assert!( violation( result ) );
+}
+
+
+#[ test ] fn test_warn()
+{
+	fn warn( _: &EkkeCfgError ) { WARNINGS.fetch_add( 1, Ordering::SeqCst ); }
+
+	let tmp  = TempDir::new( "warn" );
+	let path = tmp.file( "config.yml", "a: 1", 0o666 );
+
+	let cfg: Config<Value> = ConfigBuilder::new()
+
+		.file       ( Layer::DEFAULT, &path                     )
+		.file_policy( FilePolicy::new().warn_with( warn )       )
+		.build().unwrap();
+
+	assert_eq!( cfg.get()[ "a" ], 1 );
+	assert_eq!( WARNINGS.load( Ordering::SeqCst ), 1 );
+}
+
+
+#[ test ] fn test_include_and_reload()
+{
+	let tmp = TempDir::new( "include" );
+
+	let included = tmp.file( "included.yml", "b: 2"                    , 0o644 );
+	let path     = tmp.file( "config.yml"  , "a: !include included.yml", 0o644 );
+
+	let mut cfg: Config<Value> = ConfigBuilder::new()
+
+		.file       ( Layer::DEFAULT, &path )
+		.file_policy( FilePolicy::new()     )
+		.build().unwrap();
+
+	assert_eq!( cfg.get()[ "a" ][ "b" ], 2 );
+
+	// Included files are checked as well, also on reload.
+	//
+	fs::set_permissions( &included, fs::Permissions::from_mode( 0o666 ) ).unwrap();
+
+	assert!( cfg.reload().is_err() );
+
+	cfg.set_file_policy( None );
+
+	assert!( cfg.reload().is_ok() );
+}
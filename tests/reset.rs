@@ -0,0 +1,192 @@
+use serde_yaml  :: { Mapping, from_str                                        } ;
+use ekke_config :: { Config, ConfigBuilder, EkkeResult, Layer, Pointer, Source } ;
+use std         :: { sync::{ Arc, Mutex }                                      } ;
+
+mod common;
+use common::*;
+
+
+const DEFAULT: &str =
+"
+my_app:
+  db_path: data/db.sqlite
+  log_lvl: debug
+
+other_comp:
+  primes: [ 1, 3, 5, 7 ]
+  algo  : fournier
+";
+
+
+// A source which data can be changed from the outside.
+//
+#[ derive( Debug, Clone ) ]
+//
+struct Table
+{
+	name: &'static str                          ,
+	data: Arc< Mutex< Option< &'static str > > >,
+}
+
+
+impl Table
+{
+	fn new( name: &'static str, data: &'static str ) -> Self
+	{
+		Self { name, data: Arc::new( Mutex::new( Some( data ) ) ) }
+	}
+
+
+	fn set( &self, data: Option< &'static str > )
+	{
+		*self.data.lock().unwrap() = data;
+	}
+}
+
+
+impl Source for Table
+{
+	fn name      ( &self ) -> &str { self.name }
+	fn optional  ( &self ) -> bool { true      }
+	fn reloadable( &self ) -> bool { true      }
+
+	fn load( &self ) -> EkkeResult< Option< Mapping > >
+	{
+		match *self.data.lock().unwrap()
+		{
+			Some( input ) => Ok( Some( from_str( input )? ) ),
+			None          => Ok( None                       ),
+		}
+	}
+}
+
+
+fn tables( user: &Table, extra: &Table ) -> Config<Settings>
+{
+	ConfigBuilder::new()
+
+		.string( Layer::DEFAULT, DEFAULT )
+		.source( user .clone()           )
+		.source( extra.clone()           )
+
+		.build().unwrap()
+}
+
+
+
+#[ test ] fn test_clear_runtime()
+{
+	let mut cfg = runtime_data();
+
+	assert_eq!( cfg.get().my_app.log_lvl, "info" );
+
+	cfg.clear_runtime().unwrap();
+
+	assert!( cfg.runtime().is_none() );
+	assert_eq!( cfg.get().my_app.log_lvl, "warn" );
+
+	// Clearing it again is fine.
+	//
+	cfg.clear_runtime().unwrap();
+}
+
+
+#[ test ] fn test_reset_userset_file()
+{
+	let mut cfg = file_data();
+
+	cfg.merge_userset( "my_app: { log_lvl: info }" ).unwrap();
+	cfg.reset_userset().unwrap();
+
+	assert_eq!( cfg.get().my_app.log_lvl, "warn" );
+	assert_eq!( cfg.userset().unwrap().jptr( "/my_app/log_lvl" ).unwrap(), "warn" );
+}
+
+
+#[ test ] fn test_reset_userset_source()
+{
+	let user  = Table::new( Layer::USERSET, "my_app: { log_lvl: warn }" );
+	let extra = Table::new( "extra"       , "{}"                        );
+
+	let mut cfg = tables( &user, &extra );
+
+	cfg.merge_userset( "other_comp: { algo: gauss }" ).unwrap();
+
+	// Unlike revert_userset, this sees the changes on disk.
+	//
+	user.set( Some( "my_app: { log_lvl: error }" ) );
+	cfg.reset_userset().unwrap();
+
+	assert_eq!( cfg.get().my_app.log_lvl  , "error"    );
+	assert_eq!( cfg.get().other_comp.algo , "fournier" );
+
+	user.set( None );
+	cfg.reset_userset().unwrap();
+
+	assert!( cfg.userset().is_none() );
+	assert_eq!( cfg.get().my_app.log_lvl, "debug" );
+}
+
+
+#[ test ] fn test_remove_key()
+{
+	let mut cfg = file_data();
+
+	assert!( cfg.remove_key( Layer::USERSET, "/other_comp/algo" ).unwrap() );
+
+	assert_eq!( cfg.get().other_comp.algo, "fournier" );
+	assert_eq!( cfg.get().my_app.log_lvl , "warn"     );
+
+	assert!( !cfg.remove_key( Layer::USERSET, "/other_comp/algo" ).unwrap() );
+	assert!( !cfg.remove_key( Layer::RUNTIME, "/my_app"          ).unwrap() );
+}
+
+
+#[ test ] fn test_remove_key_directive()
+{
+	let mut cfg = file_data();
+
+	cfg.merge_runtime( "other_comp: { primes: !append [ 11 ] }" ).unwrap();
+	assert_eq!( cfg.get().other_comp.primes, vec![ 1, 3, 5, 7, 11, 11 ] );
+
+	// The directive goes together with the value.
+	//
+	assert!( cfg.remove_key( Layer::RUNTIME, "/other_comp/primes" ).unwrap() );
+
+	assert!( cfg.layer( Layer::RUNTIME ).unwrap().directives().is_empty() );
+	assert_eq!( cfg.get().other_comp.primes, vec![ 1, 3, 5, 7, 11 ] );
+}
+
+
+#[ test ] fn test_remove_key_required()
+{
+	let mut cfg = file_data();
+
+	// The defaults have the only value for it.
+	//
+	assert!( cfg.remove_key( Layer::DEFAULT, "/my_app/db_path" ).is_err() );
+	assert_eq!( cfg.get().my_app.db_path, "data/db.sqlite" );
+}
+
+
+#[ test ] fn test_remove_source()
+{
+	let user  = Table::new( Layer::USERSET, "my_app: { log_lvl: warn  }" );
+	let extra = Table::new( "extra"       , "my_app: { log_lvl: error }" );
+
+	let mut cfg = tables( &user, &extra );
+
+	assert_eq!( cfg.get().my_app.log_lvl, "error" );
+
+	assert!( cfg.remove_source( "extra" ).unwrap() );
+	assert_eq!( cfg.get().my_app.log_lvl, "warn" );
+
+	// It doesn't come back on reload.
+	//
+	cfg.reload().unwrap();
+
+	let names: Vec<&str> = cfg.layers().iter().map( Layer::name ).collect();
+	assert_eq!( names, vec![ "default", "userset" ] );
+
+	assert!( !cfg.remove_source( "extra" ).unwrap() );
+}
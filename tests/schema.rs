@@ -0,0 +1,137 @@
+#![ cfg( feature = "schema" ) ]
+
+use ekke_config :: { Config, Secret              } ;
+use schemars    :: { JsonSchema                  } ;
+use serde       :: { Serialize, Deserialize      } ;
+use serde_json  :: { json                        } ;
+use std         :: { convert::TryFrom            } ;
+
+
+/// The settings of the test app.
+//
+#[ derive( Serialize, Deserialize, JsonSchema, Debug, Clone ) ]
+//
+struct Settings
+{
+	/// Options for the app itself.
+	//
+	my_app: MyApp,
+
+	/// Components to load.
+	//
+	plugins: Vec< Plugin >,
+
+	token: Option< String >,
+}
+
+
+#[ derive( Serialize, Deserialize, JsonSchema, Debug, Clone ) ]
+//
+struct MyApp
+{
+	/// Where to store data.
+	//
+	db_path: String,
+
+	/// How much to log.
+	//
+	log_lvl: String,
+}
+
+
+#[ derive( Serialize, Deserialize, JsonSchema, Debug, Clone ) ]
+//
+struct Plugin
+{
+	name   : String,
+	enabled: bool  ,
+}
+
+
+const DEFAULTS: &str = "
+
+sensitive: [ /token ]
+
+default:
+
+  my_app:
+    db_path: data/db.sqlite
+    log_lvl: debug
+
+  plugins:
+    - { name: one, enabled: true }
+
+  token: hunter2
+";
+
+
+fn config() -> Config<Settings>
+{
+	Config::try_from( DEFAULTS ).unwrap()
+}
+
+
+
+#[ test ] fn test_schema()
+{
+	let schema = config().schema().unwrap();
+	let my_app = &schema[ "properties" ][ "my_app" ];
+
+	assert_eq!( schema[ "description" ], "The settings of the test app." );
+	assert_eq!( schema[ "required"    ], json!([ "my_app", "plugins" ])  );
+	assert_eq!( my_app[ "required"    ], json!([ "db_path", "log_lvl" ]) );
+
+	assert_eq!( my_app[ "description" ]                           , "Options for the app itself." );
+	assert_eq!( my_app[ "properties"  ][ "db_path" ][ "default"     ], "data/db.sqlite"           );
+	assert_eq!( my_app[ "properties"  ][ "db_path" ][ "description" ], "Where to store data."     );
+	assert_eq!( my_app[ "properties"  ][ "log_lvl" ][ "default"     ], "debug"                    );
+	assert_eq!( schema[ "properties"  ][ "plugins" ][ "default"     ], json!([ { "name": "one", "enabled": true } ]) );
+}
+
+
+#[ test ] fn test_userset_schema()
+{
+	let schema = config().userset_schema().unwrap();
+	let my_app = &schema[ "properties" ][ "my_app" ];
+
+	assert!( schema[ "required" ].is_null() );
+	assert!( my_app[ "required" ].is_null() );
+
+	// Elements of sequences replace those of the defaults, so they must be complete.
+	//
+	assert_eq!( schema[ "properties" ][ "plugins" ][ "items" ][ "required" ], json!([ "enabled", "name" ]) );
+
+	assert_eq!( my_app[ "properties" ][ "log_lvl" ][ "default" ], "debug" );
+}
+
+
+#[ test ] fn test_sensitive()
+{
+	let schema = config().schema().unwrap();
+
+	assert!( schema[ "properties" ][ "token" ][ "default" ].is_null() );
+	assert!( !schema.to_string().contains( "hunter2" ) );
+}
+
+
+#[ test ] fn test_secret()
+{
+	#[ derive( Serialize, Deserialize, JsonSchema, Debug, Clone ) ]
+	//
+	struct Db
+	{
+		/// The password of the database.
+		//
+		password: Secret< String >,
+	}
+
+	let cfg: Config<Db> = Config::try_from( "default: { password: hunter2 }" ).unwrap();
+	let schema          = cfg.schema().unwrap();
+
+	// A secret is described like the value it holds, but its default is not shown.
+	//
+	assert_eq!( schema[ "properties" ][ "password" ][ "type"        ], "string"                       );
+	assert_eq!( schema[ "properties" ][ "password" ][ "description" ], "The password of the database." );
+
+	assert!( !schema.to_string().contains( "hunter2" ) );
+}
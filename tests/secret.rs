@@ -0,0 +1,142 @@
+use ekke_config :: { Config, ConfigBuilder, Layer, Secret } ;
+use serde       :: { Serialize, Deserialize               } ;
+use std         :: { convert::TryFrom                     } ;
+
+
+#[ derive( Serialize, Deserialize, Debug, Clone ) ]
+//
+struct Settings
+{
+	db: DbOpts,
+}
+
+#[ derive( Serialize, Deserialize, Debug, Clone ) ]
+//
+struct DbOpts
+{
+	user    : String        ,
+	password: Secret<String>,
+	token   : String        ,
+}
+
+
+const DEFAULT: &str =
+"
+sensitive: [ /db/token ]
+
+default:
+  db:
+    user    : admin
+    password: hunter2
+    token   : s3cr3t
+";
+
+
+
+#[ test ] fn test_secret()
+{
+	let cfg: Config<Settings> = Config::try_from( DEFAULT ).unwrap();
+
+	assert_eq!( cfg.get().db.password.expose(), "hunter2"        );
+	assert_eq!( cfg.sensitive()               , &[ "/db/token" ] );
+
+	let debug = format!( "{:?}", cfg );
+
+	assert!( !debug.contains( "hunter2" ) );
+	assert!( !debug.contains( "s3cr3t"  ) );
+	assert!(  debug.contains( "admin"   ) );
+}
+
+
+#[ test ] fn test_redacted()
+{
+	let cfg: Config<Settings> = Config::try_from( DEFAULT ).unwrap();
+
+	let redacted = cfg.redacted().unwrap();
+
+	assert_eq!( redacted[ "db" ][ "user"     ], "admin" );
+	assert_eq!( redacted[ "db" ][ "password" ], "***"   );
+	assert_eq!( redacted[ "db" ][ "token"    ], "***"   );
+}
+
+
+#[ test ] fn test_sensitive()
+{
+	// A sensitive pointer covers everything below it.
+	//
+	let mut cfg: Config<Settings> = ConfigBuilder::new()
+
+		.string   ( Layer::DEFAULT, "db: { user: admin, password: hunter2, token: s3cr3t }" )
+		.sensitive( "/db" )
+
+		.build().unwrap();
+
+	let debug = format!( "{:?}", cfg );
+
+	assert!( !debug.contains( "admin"   ) );
+	assert!( !debug.contains( "hunter2" ) );
+
+	// Error messages don't show sensitive strings.
+	//
+	let err = cfg.merge_runtime( "db: { token: '${nope' }" ).unwrap_err();
+
+	assert!( !err.to_string().contains( "nope" ) );
+
+	cfg.add_sensitive( "/other" );
+
+	assert_eq!( cfg.sensitive(), &[ "/db", "/other" ] );
+}
+
+
+#[ test ] fn test_deserialize_error()
+{
+	#[ derive( Serialize, Deserialize, Debug, Clone ) ]
+	//
+	struct Server
+	{
+		port: u16        ,
+		pin : Secret<u16>,
+	}
+
+	// Values that don't deserialize are not shown when they are sensitive or secret.
+	//
+	let mut cfg: Config<Server> = ConfigBuilder::new()
+
+		.string   ( Layer::DEFAULT, "{ port: 80, pin: 1234 }" )
+		.sensitive( "/port" )
+
+		.build().unwrap();
+
+	let err = cfg.merge_runtime( "port: hunter2" ).unwrap_err();
+
+	assert!( !err.to_string().contains( "hunter2" ) );
+	assert!(  err.to_string().contains( "u16"     ) );
+
+	let err = cfg.merge_runtime( "pin: s3cr3t" ).unwrap_err();
+
+	assert!( !err.to_string().contains( "s3cr3t" ) );
+
+	assert_eq!( cfg.get().port         , 80   );
+	assert_eq!( *cfg.get().pin.expose(), 1234 );
+}
+
+
+#[ test ] fn test_layers()
+{
+	// Listing the layers or the locks does not show sensitive values either.
+	//
+	let mut cfg: Config<Settings> = Config::try_from( DEFAULT ).unwrap();
+
+	cfg.merge_runtime( "db: { token: !locked t0k3n }" ).unwrap();
+
+	for listing in &[ format!( "{:?}", cfg.layers() ), format!( "{:?}", cfg.locks() ) ]
+	{
+		assert!( !listing.contains( "s3cr3t"  ) );
+		assert!( !listing.contains( "t0k3n"   ) );
+		assert!( !listing.contains( "hunter2" ) );
+	}
+
+	// The data itself is still there.
+	//
+	assert_eq!( cfg.runtime().unwrap()[ "db" ][ "token" ], "t0k3n" );
+}
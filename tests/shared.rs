@@ -0,0 +1,92 @@
+use ekke_config :: { SharedConfig } ;
+use std         :: { thread      } ;
+
+mod common;
+use common::*;
+
+
+
+#[ test ] fn test_snapshot()
+{
+	let shared = SharedConfig::new( file_data() );
+	let before = shared.get();
+
+	assert_eq!( shared.generation(), 0 );
+
+	shared.merge_runtime( "my_app: { log_lvl: info }" ).unwrap();
+
+	// The old snapshot is not affected
+	//
+	assert_eq!( before.my_app.log_lvl       , "warn" );
+	assert_eq!( shared.get().my_app.log_lvl , "info" );
+	assert_eq!( shared.generation()         , 1      );
+
+	shared.merge_userset( "other_comp: { algo: Gauss }" ).unwrap();
+
+	assert_eq!( shared.get().other_comp.algo, "Gauss" );
+	assert_eq!( shared.generation()         , 2       );
+}
+
+
+#[ test ] fn test_failed_update()
+{
+	let shared = SharedConfig::new( file_data() );
+
+	assert!( shared.merge_runtime( "my_app: { log_lvl: [ not, a, string ] }" ).is_err() );
+
+	assert_eq!( shared.get().my_app.log_lvl, "warn" );
+	assert_eq!( shared.generation()        , 0      );
+}
+
+
+#[ test ] fn test_threads()
+{
+	let shared = SharedConfig::new( file_data() );
+	let reader = shared.clone();
+
+	let handle = thread::spawn( move ||
+	{
+		while reader.generation() == 0 {}
+
+		reader.get().my_app.log_lvl.clone()
+	});
+
+	shared.merge_runtime( "my_app: { log_lvl: error }" ).unwrap();
+
+	assert_eq!( handle.join().unwrap(), "error" );
+	assert_eq!( shared.read( |cfg| cfg.get().my_app.log_lvl.clone() ), "error" );
+}
+
+
+#[ test ] fn test_consistent_snapshot()
+{
+	let shared = SharedConfig::new( file_data() );
+	let writer = shared.clone();
+
+	let handle = thread::spawn( move ||
+	{
+		for lvl in &[ "info", "error", "trace" ]
+		{
+			writer.merge_runtime( &format!( "my_app: {{ log_lvl: {} }}", lvl ) ).unwrap();
+		}
+	});
+
+	// The generation always belongs to the settings it comes with.
+	//
+	loop
+	{
+		let ( generation, settings ) = shared.snapshot();
+
+		let expected = [ "warn", "info", "error", "trace" ][ generation ];
+
+		assert_eq!( settings.my_app.log_lvl, expected );
+
+		if generation == 3 { break }
+	}
+
+	handle.join().unwrap();
+
+	// Updates keep the history, even though they work on a copy.
+	//
+	assert_eq!( shared.read( |cfg| cfg.history_len() ), 4 );
+}
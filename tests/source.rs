@@ -0,0 +1,159 @@
+use serde_yaml  :: { Mapping, from_str                               } ;
+use ekke_config :: { Config, ConfigBuilder, EkkeResult, Layer, Source } ;
+use std         :: { sync::{ Arc, Mutex }                             } ;
+
+mod common;
+use common::*;
+
+
+const DEFAULT: &str =
+"
+my_app:
+  db_path: data/db.sqlite
+  log_lvl: debug
+
+other_comp:
+  primes: [ 1, 3, 5, 7 ]
+  algo  : fournier
+";
+
+
+// A source which data can be changed from the outside, like a database table.
+//
+#[ derive( Debug, Clone ) ]
+//
+struct Table
+{
+	name    : &'static str                          ,
+	optional: bool                                  ,
+	data    : Arc< Mutex< Option< &'static str > > >,
+}
+
+
+impl Table
+{
+	fn new( name: &'static str, optional: bool, data: Option< &'static str > ) -> Self
+	{
+		Self { name, optional, data: Arc::new( Mutex::new( data ) ) }
+	}
+
+
+	fn set( &self, data: Option< &'static str > )
+	{
+		*self.data.lock().unwrap() = data;
+	}
+}
+
+
+impl Source for Table
+{
+	fn name      ( &self ) -> &str             { self.name                              }
+	fn origin    ( &self ) -> Option< String > { Some( format!( "db://{}", self.name ) ) }
+	fn optional  ( &self ) -> bool             { self.optional                          }
+	fn reloadable( &self ) -> bool             { true                                   }
+
+	fn load( &self ) -> EkkeResult< Option< Mapping > >
+	{
+		match *self.data.lock().unwrap()
+		{
+			Some( input ) => Ok( Some( from_str( input )? ) ),
+			None          => Ok( None                       ),
+		}
+	}
+}
+
+
+
+#[ test ] fn test_custom()
+{
+	let table = Table::new( "table", false, Some( "my_app: { log_lvl: info }" ) );
+
+	let cfg: Config<Settings> = ConfigBuilder::new()
+
+		.string( Layer::DEFAULT, DEFAULT )
+		.source( table                   )
+
+		.build().unwrap();
+
+	assert_eq!( cfg.get().my_app.log_lvl              , "info"               );
+	assert_eq!( cfg.layer( "table" ).unwrap().origin(), Some( "db://table" ) );
+}
+
+
+#[ test ] fn test_required()
+{
+	let table = Table::new( "table", false, None );
+
+	let cfg = ConfigBuilder::new()
+
+		.string( Layer::DEFAULT, DEFAULT )
+		.source( table                   )
+
+		.build::<Settings>();
+
+	assert!( cfg.is_err() );
+}
+
+
+#[ test ] fn test_reload()
+{
+	let system = Table::new( "system", true, None                                );
+	let user   = Table::new( "user"  , true, Some( "my_app: { log_lvl: warn }" ) );
+
+	let mut cfg: Config<Settings> = ConfigBuilder::new()
+
+		.string( Layer::DEFAULT, DEFAULT )
+		.source( system.clone()          )
+		.source( user  .clone()          )
+
+		.build().unwrap();
+
+	cfg.merge_runtime( "other_comp: { algo: euler }" ).unwrap();
+
+	assert_eq!( cfg.get().my_app.log_lvl, "warn" );
+
+	// The optional system source appears, it should go below user.
+	//
+	system.set( Some( "my_app: { log_lvl: error, db_path: /var/db.sqlite }" ) );
+	user  .set( None                                                        );
+
+	cfg.reload().unwrap();
+
+	let names: Vec<&str> = cfg.layers().iter().map( Layer::name ).collect();
+	assert_eq!( names, vec![ "default", "system", "runtime" ] );
+
+	assert_eq!( cfg.get().my_app.log_lvl , "error"          );
+	assert_eq!( cfg.get().my_app.db_path , "/var/db.sqlite" );
+	assert_eq!( cfg.get().other_comp.algo, "euler"          );
+
+	user.set( Some( "my_app: { log_lvl: trace }" ) );
+
+	cfg.reload().unwrap();
+
+	let names: Vec<&str> = cfg.layers().iter().map( Layer::name ).collect();
+	assert_eq!( names, vec![ "default", "system", "user", "runtime" ] );
+
+	assert_eq!( cfg.get().my_app.log_lvl, "trace" );
+}
+
+
+#[ test ] fn test_failed_reload()
+{
+	let user = Table::new( "user", true, Some( "my_app: { log_lvl: warn }" ) );
+
+	let mut cfg: Config<Settings> = ConfigBuilder::new()
+
+		.string( Layer::DEFAULT, DEFAULT )
+		.source( user.clone()            )
+
+		.build().unwrap();
+
+	// The new data does not deserialize, so the layers keep the old data.
+	//
+	user.set( Some( "my_app: { log_lvl: [ loud ] }" ) );
+
+	assert!( cfg.reload().is_err() );
+
+	assert_eq!( cfg.get().my_app.log_lvl, "warn" );
+	assert_eq!( cfg.layer( "user" ).unwrap().data()[ &"my_app".into() ][ "log_lvl" ], "warn" );
+}
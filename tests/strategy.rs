@@ -0,0 +1,151 @@
+use ekke_config :: { Config, ConfigBuilder, Layer, MergeStrategy } ;
+use serde       :: { Serialize, Deserialize                     } ;
+use std         :: { convert::TryFrom, path::PathBuf            } ;
+
+mod common;
+use common::*;
+
+
+#[ derive( Serialize, Deserialize, Debug, Clone, PartialEq ) ]
+//
+struct Plugin
+{
+	name   : String,
+	enabled: bool  ,
+}
+
+
+#[ derive( Serialize, Deserialize, Debug, Clone ) ]
+//
+struct Plugins
+{
+	plugins: Vec< Plugin >,
+}
+
+
+fn config( strategy: MergeStrategy ) -> Config<Settings>
+{
+	ConfigBuilder::new()
+
+		.string( Layer::DEFAULT, "{ my_app: { db_path: data/db.sqlite, log_lvl: debug }, other_comp: { primes: [ 1, 3, 5, 7 ], algo: euler } }" )
+
+		.merge_strategy( "/other_comp/primes", strategy )
+
+		.build().unwrap()
+}
+
+
+fn primes( strategy: MergeStrategy, runtime: &str ) -> Vec<usize>
+{
+	let mut cfg = config( strategy );
+
+	cfg.merge_runtime( runtime ).unwrap();
+
+	cfg.get().other_comp.primes.clone()
+}
+
+
+
+#[ test ] fn test_defaults_file()
+{
+	let path = PathBuf::from( env!( "CARGO_MANIFEST_DIR" ) ).join( "data/merge/defaults.yml" );
+
+	let cfg: Config<Settings> = Config::try_from( &path ).unwrap();
+
+	assert_eq!( cfg.get().other_comp.primes, vec![ 1, 3, 5, 7, 11 ] );
+	assert_eq!( cfg.merge_strategies()[ "/other_comp/primes" ], MergeStrategy::Append );
+}
+
+
+#[ test ] fn test_dir()
+{
+	let dir = |path: &str, strategy: Option< MergeStrategy >|
+	{
+		let mut builder = ConfigBuilder::new()
+
+			.string( Layer::DEFAULT, "{ my_app: { db_path: data/db.sqlite, log_lvl: debug }, other_comp: { primes: [ 1, 3, 5, 7 ], algo: euler } }" )
+			.dir   ( "system.d", PathBuf::from( env!( "CARGO_MANIFEST_DIR" ) ).join( path ) )
+		;
+
+		if let Some( strategy ) = strategy
+		{
+			builder = builder.merge_strategy( "/other_comp/primes", strategy );
+		}
+
+		let cfg: Config<Settings> = builder.build().unwrap();
+
+		cfg.get().other_comp.primes.clone()
+	};
+
+	assert_eq!( dir( "data/merge/conf.d"     , Some( MergeStrategy::Append ) ), vec![ 1, 3, 5, 7, 11, 13 ] );
+	assert_eq!( dir( "data/merge/conf.d"     , None                          ), vec![ 13                  ] );
+	assert_eq!( dir( "data/merge/directive.d", None                          ), vec![ 1, 3, 5, 7, 11, 13 ] );
+}
+
+
+#[ test ] fn test_strategies()
+{
+	assert_eq!( primes( MergeStrategy::Replace, "other_comp: { primes: [ 11, 1 ] }" ), vec![ 11, 1              ] );
+	assert_eq!( primes( MergeStrategy::Append , "other_comp: { primes: [ 11, 1 ] }" ), vec![ 1, 3, 5, 7, 11, 1  ] );
+	assert_eq!( primes( MergeStrategy::Prepend, "other_comp: { primes: [ 11, 1 ] }" ), vec![ 11, 1, 1, 3, 5, 7  ] );
+	assert_eq!( primes( MergeStrategy::Union  , "other_comp: { primes: [ 11, 1 ] }" ), vec![ 1, 3, 5, 7, 11     ] );
+
+	// Setting a strategy later regenerates the settings.
+	//
+	let mut cfg: Config<Settings> = common::basic_data();
+
+	cfg.merge_runtime( "other_comp: { primes: [ 13 ] }" ).unwrap();
+	cfg.set_merge_strategy( "/other_comp/primes", MergeStrategy::Union ).unwrap();
+
+	assert_eq!( cfg.get().other_comp.primes, vec![ 1, 3, 5, 7, 11, 13 ] );
+}
+
+
+#[ test ] fn test_failed_strategy()
+{
+	// Replacing my_app with the runtime layer loses the db_path, so the strategy is not kept.
+	//
+	let mut cfg: Config<Settings> = common::basic_data();
+
+	cfg.merge_runtime( "my_app: { log_lvl: info }" ).unwrap();
+
+	assert!( cfg.set_merge_strategy( "/my_app", MergeStrategy::Replace ).is_err() );
+	assert!( !cfg.merge_strategies().contains_key( "/my_app" ) );
+
+	// Later changes are not affected.
+	//
+	cfg.merge_runtime( "my_app: { log_lvl: warn }" ).unwrap();
+
+	assert_eq!( cfg.get().my_app.log_lvl, "warn" );
+}
+
+
+#[ test ] fn test_same_layer()
+{
+	// Merging into the same layer twice uses the strategy as well.
+	//
+	let mut cfg = config( MergeStrategy::Append );
+
+	cfg.merge_runtime( "other_comp: { primes: [ 11 ] }" ).unwrap();
+	cfg.merge_runtime( "other_comp: { primes: [ 13 ] }" ).unwrap();
+
+	assert_eq!( cfg.runtime().unwrap()[ "other_comp" ][ "primes" ], serde_yaml::from_str::<serde_yaml::Value>( "[ 11, 13 ]" ).unwrap() );
+	assert_eq!( cfg.get().other_comp.primes, vec![ 1, 3, 5, 7, 11, 13 ] );
+}
+
+
+#[ test ] fn test_by_key()
+{
+	let cfg: Config<Plugins> = ConfigBuilder::new()
+
+		.string( Layer::DEFAULT, "plugins: [ { name: spell, enabled: true }, { name: vim, enabled: true } ]" )
+		.string( Layer::USERSET, "plugins: [ { name: vim, enabled: false }, { name: emacs, enabled: true } ]" )
+
+		.merge_strategy( "/plugins", MergeStrategy::ByKey( "name".to_string() ) )
+
+		.build().unwrap();
+
+	let plugins: Vec<( &str, bool )> = cfg.get().plugins.iter().map( |p| ( p.name.as_str(), p.enabled ) ).collect();
+
+	assert_eq!( plugins, vec![ ( "spell", true ), ( "vim", false ), ( "emacs", true ) ] );
+}
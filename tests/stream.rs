@@ -0,0 +1,64 @@
+#![ cfg( feature = "stream" ) ]
+
+use ekke_config :: { SharedConfig                  } ;
+use futures     :: { executor::block_on, StreamExt } ;
+
+mod common;
+use common::*;
+
+
+
+#[ test ] fn test_updates()
+{
+	let shared      = SharedConfig::new( file_data() );
+	let mut updates = shared.updates();
+
+	shared.merge_runtime( "my_app: { log_lvl: info }"   ).unwrap();
+	shared.merge_userset( "other_comp: { algo: Gauss }" ).unwrap();
+
+	block_on( async
+	{
+		assert_eq!( updates.next().await.unwrap().my_app.log_lvl , "info"  );
+		assert_eq!( updates.next().await.unwrap().other_comp.algo, "Gauss" );
+	});
+
+	// The stream ends when the config goes away.
+	//
+	drop( shared );
+
+	assert!( block_on( updates.next() ).is_none() );
+}
+
+
+#[ test ] fn test_watch()
+{
+	let shared    = SharedConfig::new( file_data() );
+	let mut watch = shared.watch();
+
+	assert_eq!( watch.borrow().my_app.log_lvl, "warn" );
+
+	shared.merge_runtime( "my_app: { log_lvl: info  }" ).unwrap();
+	shared.merge_runtime( "my_app: { log_lvl: error }" ).unwrap();
+
+	// Only the latest settings are seen.
+	//
+	assert_eq!( block_on( watch.changed() ).my_app.log_lvl, "error" );
+
+	shared.reload().unwrap();
+
+	assert_eq!( block_on( watch.changed() ).my_app.log_lvl, "error" );
+	assert_eq!( watch.borrow().other_comp.algo            , "euler" );
+}
+
+
+#[ test ] fn test_watch_thread()
+{
+	let shared    = SharedConfig::new( file_data() );
+	let mut watch = shared.watch();
+
+	let handle = std::thread::spawn( move || block_on( watch.changed() ).my_app.log_lvl.clone() );
+
+	shared.merge_runtime( "my_app: { log_lvl: trace }" ).unwrap();
+
+	assert_eq!( handle.join().unwrap(), "trace" );
+}
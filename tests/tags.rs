@@ -0,0 +1,203 @@
+use ekke_config :: { Config, ConfigBuilder, CmdTag, EkkeCfgError, EkkeResult, Layer, TagResolver } ;
+use serde_yaml  :: { Mapping, Value                                                             } ;
+use std         :: { convert::TryFrom, env, iter::FromIterator                                  } ;
+
+mod common;
+use common::*;
+
+
+const DEFAULT: &str =
+"
+my_app:
+  db_path: data/db.sqlite
+  log_lvl: debug
+
+other_comp:
+  primes: [ 1, 3, 5, 7 ]
+  algo  : fournier
+";
+
+
+#[ derive( Debug ) ]
+//
+struct Upper;
+
+impl TagResolver for Upper
+{
+	fn tag( &self ) -> &str { "!upper" }
+
+	fn resolve( &self, value: &Value ) -> EkkeResult< Value >
+	{
+		Ok( Value::String( value.as_str().unwrap_or_default().to_uppercase() ) )
+	}
+}
+
+
+
+#[ test ] fn test_env()
+{
+	env::set_var( "EKKE_TAGS_LVL", "trace" );
+
+	let cfg: Config<Settings> = ConfigBuilder::new()
+
+		.string( Layer::DEFAULT, DEFAULT                            )
+		.string( Layer::USERSET, "my_app: { log_lvl: !env EKKE_TAGS_LVL }" )
+
+		.build().unwrap();
+
+	assert_eq!( cfg.get().my_app.log_lvl, "trace" );
+
+	// The layer holds the resolved value.
+	//
+	assert_eq!( cfg.userset().unwrap()[ "my_app" ][ "log_lvl" ], "trace" );
+
+	let err = ConfigBuilder::new()
+
+		.string( Layer::DEFAULT, "my_app: { log_lvl: !env EKKE_TAGS_NOT_SET }" )
+		.build::<Value>().unwrap_err();
+
+	match err.find_root_cause().downcast_ref::< EkkeCfgError >()
+	{
+		Some( EkkeCfgError::UnsetVariable( var ) ) => assert_eq!( var, "EKKE_TAGS_NOT_SET" ),
+		_                                          => panic!( "expected an unset variable, got: {}", err ),
+	}
+}
+
+
+#[ test ] fn test_file()
+{
+	let mut cfg = common::file_data();
+
+	cfg.merge_runtime( "my_app: { db_path: !file data/tags/db_path }" ).unwrap();
+
+	assert_eq!( cfg.get().my_app.db_path, "data/secret.sqlite" );
+
+	// Tags also work in the defaults file format.
+	//
+	let cfg: Config<Settings> = Config::try_from
+	(
+"
+default:
+  my_app:
+    db_path: !file data/tags/db_path
+    log_lvl: debug
+
+  other_comp:
+    primes: [ 1, 3, 5, 7 ]
+    algo  : fournier
+"
+	).unwrap();
+
+	assert_eq!( cfg.get().my_app.db_path, "data/secret.sqlite" );
+}
+
+
+#[ test ] fn test_file_literal()
+{
+	// The content of a file is not interpolated.
+	//
+	let mut cfg = common::file_data();
+
+	cfg.merge_runtime( "my_app: { db_path: !file data/tags/placeholder, log_lvl: !file data/tags/escaped }" ).unwrap();
+
+	assert_eq!( cfg.get().my_app.db_path, "pa${x}ss"  );
+	assert_eq!( cfg.get().my_app.log_lvl, "pa$${x}ss" );
+}
+
+
+#[ test ] fn test_cmd()
+{
+	let input = "{ my_app: { log_lvl: !cmd [ printf, info ] }, other_comp: { algo: !cmd echo gauss } }";
+
+	// Not enabled by default.
+	//
+	assert!
+	(
+		ConfigBuilder::new()
+
+			.string( Layer::DEFAULT, DEFAULT )
+			.string( Layer::USERSET, input   )
+
+			.build::<Settings>().is_err()
+	);
+
+	let cfg: Config<Settings> = ConfigBuilder::new()
+
+		.string  ( Layer::DEFAULT, DEFAULT )
+		.string  ( Layer::USERSET, input   )
+		.resolver( CmdTag                  )
+
+		.build().unwrap();
+
+	assert_eq!( cfg.get().my_app.log_lvl  , "info"  );
+	assert_eq!( cfg.get().other_comp.algo , "gauss" );
+
+	assert!
+	(
+		ConfigBuilder::new()
+
+			.string  ( Layer::DEFAULT, "algo: !cmd exit 1" )
+			.resolver( CmdTag                              )
+
+			.build::<Value>().is_err()
+	);
+}
+
+
+#[ test ] fn test_custom()
+{
+	let cfg: Config<Settings> = ConfigBuilder::new()
+
+		.string  ( Layer::DEFAULT, DEFAULT                                   )
+		.string  ( Layer::USERSET, "other_comp: { algo: !upper euler }"      )
+		.resolver( Upper                                                     )
+
+		.build().unwrap();
+
+	assert_eq!( cfg.get().other_comp.algo, "EULER" );
+}
+
+
+#[ test ] fn test_only_yaml_tags()
+{
+	let mut fake = Mapping::new();
+	fake.insert( "!file".into(), "data/tags/db_path".into() );
+
+	// Data that doesn't come from yaml text can't pose as a tag, even when it looks like one.
+	//
+	let cfg: Config<Value> = ConfigBuilder::new()
+
+		.cli     ( "cli"  , vec![ "a={ '!file': data/tags/db_path }", "b={ '!cmd': 'echo pwned' }" ] )
+		.value   ( "value", Mapping::from_iter( vec![ ( "c".into(), Value::Mapping( fake.clone() ) ) ] ) )
+		.resolver( CmdTag )
+
+		.build().unwrap();
+
+	assert_eq!( cfg.get()[ "a" ][ "!file" ], "data/tags/db_path" );
+	assert_eq!( cfg.get()[ "b" ][ "!cmd"  ], "echo pwned"        );
+	assert_eq!( cfg.get()[ "c" ]           , Value::Mapping( fake ) );
+
+	// Neither can a quoted key in yaml.
+	//
+	let cfg: Config<Value> = ConfigBuilder::new()
+
+		.string( Layer::DEFAULT, "{ d: { '!file': data/tags/db_path }, e: { '!foo': 1 } }" )
+		.build().unwrap();
+
+	assert_eq!( cfg.get()[ "d" ][ "!file" ], "data/tags/db_path" );
+	assert_eq!( cfg.get()[ "e" ][ "!foo"  ], 1                   );
+}
+
+
+#[ test ] fn test_alias()
+{
+	// An alias brings the tags that produce the value of the anchored node along.
+	//
+	let cfg: Config<Value> = ConfigBuilder::new()
+
+		.string( Layer::DEFAULT, "{ a: &path { file: !file data/tags/db_path }, b: *path }" )
+		.build().unwrap();
+
+	assert_eq!( cfg.get()[ "a" ][ "file" ], "data/secret.sqlite" );
+	assert_eq!( cfg.get()[ "b" ][ "file" ], "data/secret.sqlite" );
+}
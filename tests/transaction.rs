@@ -0,0 +1,147 @@
+use ekke_config :: { Config, SharedConfig                  } ;
+use serde       :: { Serialize, Deserialize                } ;
+use std         :: { convert::TryFrom                      } ;
+
+mod common;
+use common::*;
+
+
+// Only valid when min <= max, so moving the range up takes two changes that are invalid on their own.
+//
+#[ derive( Serialize, Deserialize, Debug, Clone, PartialEq ) ]
+#[ serde( try_from = "RawRange" ) ]
+//
+struct Range
+{
+	min: u32,
+	max: u32,
+}
+
+
+#[ derive( Deserialize ) ]
+//
+struct RawRange
+{
+	min: u32,
+	max: u32,
+}
+
+
+impl TryFrom< RawRange > for Range
+{
+	type Error = String;
+
+	fn try_from( raw: RawRange ) -> Result< Self, String >
+	{
+		if raw.min > raw.max { return Err( format!( "min {} is bigger than max {}", raw.min, raw.max ) ) }
+
+		Ok( Range { min: raw.min, max: raw.max } )
+	}
+}
+
+
+fn range() -> Config<Range>
+{
+	Config::try_from( "default: { min: 1, max: 10 }" ).unwrap()
+}
+
+
+
+#[ test ] fn test_commit()
+{
+	let mut cfg = range();
+
+	// One change at a time doesn't work.
+	//
+	assert!( cfg.merge_runtime( "min: 20" ).is_err() );
+	assert!( cfg.runtime().is_none() );
+
+	let mut tx = cfg.transaction();
+
+	tx.merge_runtime( "min: 20" ).unwrap();
+	tx.merge_userset( "max: 30" ).unwrap();
+
+	assert_eq!( tx.layers().len(), 3 );
+
+	tx.commit().unwrap();
+
+	assert_eq!( cfg.get().min, 20 );
+	assert_eq!( cfg.get().max, 30 );
+}
+
+
+#[ test ] fn test_failed_commit()
+{
+	let mut cfg = range();
+
+	cfg.merge_runtime( "max: 15" ).unwrap();
+
+	let     old = cfg.clone();
+	let mut tx  = cfg.transaction();
+
+	tx.merge_runtime    ( "min: 20"                                          ).unwrap();
+	tx.merge_patch_layer( "runtime", "max: 12"                               ).unwrap();
+	tx.patch_layer      ( "userset", "[ { op: add, path: /max, value: 40 } ]" ).unwrap();
+
+	assert!( tx.commit().is_err() );
+
+	assert_eq!( cfg          , old );
+	assert_eq!( cfg.get().min, 1   );
+	assert_eq!( cfg.get().max, 15  );
+}
+
+
+#[ test ] fn test_rollback()
+{
+	let mut cfg = range();
+	let     old = cfg.clone();
+
+	{
+		let mut tx = cfg.transaction();
+
+		tx.merge_runtime( "{ min: 2, max: 3 }" ).unwrap();
+
+		// A failing change is undone on its own.
+		//
+		assert!( tx.patch_layer( "runtime", "[ { op: remove, path: /nothing } ]" ).is_err() );
+
+		assert_eq!( tx.layers()[1].data().len(), 2 );
+
+		tx.rollback();
+	}
+
+	assert_eq!( cfg, old );
+
+	// Dropping does the same.
+	//
+	{
+		let mut tx = cfg.transaction();
+
+		tx.merge_runtime( "{ min: 2, max: 3 }" ).unwrap();
+	}
+
+	assert_eq!( cfg, old );
+}
+
+
+#[ test ] fn test_shared()
+{
+	let shared = SharedConfig::new( file_data() );
+
+	shared.transaction( |tx|
+	{
+		tx.merge_runtime( "my_app: { log_lvl: info }"   )?;
+		tx.merge_userset( "other_comp: { algo: Gauss }" )?;
+
+		Ok(())
+
+	}).unwrap();
+
+	assert_eq!( shared.get().my_app.log_lvl , "info"  );
+	assert_eq!( shared.get().other_comp.algo, "Gauss" );
+	assert_eq!( shared.generation()         , 1       );
+
+	assert!( shared.transaction( |tx| tx.merge_runtime( "other_comp: { primes: many }" ) ).is_err() );
+
+	assert_eq!( shared.generation(), 1 );
+}
@@ -0,0 +1,100 @@
+use ekke_config :: { Config, EkkeCfgError, Layer } ;
+use serde       :: { Serialize, Deserialize       } ;
+use std         :: { collections::BTreeMap, convert::TryFrom } ;
+
+mod common;
+
+
+#[ derive( Serialize, Deserialize, Debug, Clone ) ]
+//
+struct Opts
+{
+	log_file: Option< String >,
+	log_lvl : String          ,
+	plugins : BTreeMap< String, String >,
+}
+
+
+fn opts() -> Config<Opts>
+{
+	let def =
+"
+default:
+  log_file: /var/log/my_app.log
+  log_lvl : debug
+
+  plugins:
+    spell: /usr/lib/spell.so
+    vim  : /usr/lib/vim.so
+";
+
+	Config::try_from( def ).unwrap()
+}
+
+
+fn unset_pointer( err: failure::Error ) -> String
+{
+	match err.downcast_ref::<EkkeCfgError>()
+	{
+		Some( EkkeCfgError::UnsetConfig( ptr ) ) => ptr.clone(),
+		_                                        => panic!( "unexpected error: {}", err ),
+	}
+}
+
+
+
+#[ test ] fn test_unset()
+{
+	let mut cfg = opts();
+
+	cfg.merge_userset( "log_file: !unset\nplugins: { vim: !unset }" ).unwrap();
+
+	assert_eq!( cfg.get().log_file        , None                  );
+	assert_eq!( cfg.get().plugins.len()   , 1                     );
+	assert_eq!( cfg.get().plugins[ "spell" ], "/usr/lib/spell.so" );
+
+	assert_eq!( cfg.layer( Layer::USERSET ).unwrap().unset(), &[ "/log_file", "/plugins/vim" ] );
+
+	// A higher layer can set it again.
+	//
+	cfg.merge_runtime( "log_file: /tmp/my_app.log" ).unwrap();
+
+	assert_eq!( cfg.get().log_file, Some( "/tmp/my_app.log".to_string() ) );
+}
+
+
+#[ test ] fn test_unset_null()
+{
+	let mut cfg = opts();
+
+	cfg.merge_runtime( "log_file: ~" ).unwrap();
+
+	assert_eq!( cfg.get().log_file, None );
+}
+
+
+#[ test ] fn test_unset_same_layer()
+{
+	// Unsetting a value set earlier in the same layer removes it from the layer.
+	//
+	let mut cfg = opts();
+
+	cfg.merge_runtime( "plugins: { emacs: /usr/lib/emacs.so }" ).unwrap();
+	cfg.merge_runtime( "plugins: { emacs: !unset }"            ).unwrap();
+
+	assert!( cfg.runtime().unwrap()[ "plugins" ].as_mapping().unwrap().is_empty() );
+	assert!( !cfg.get().plugins.contains_key( "emacs" ) );
+}
+
+
+#[ test ] fn test_unset_required()
+{
+	let mut cfg = opts();
+
+	assert_eq!( unset_pointer( cfg.merge_runtime( "log_lvl: !unset" ).unwrap_err() ), "/log_lvl" );
+	assert_eq!( unset_pointer( cfg.merge_runtime( "log_lvl: ~"      ).unwrap_err() ), "/log_lvl" );
+
+	let mut cfg = common::basic_data();
+
+	assert_eq!( unset_pointer( cfg.merge_runtime( "other_comp: !unset" ).unwrap_err() ), "/other_comp" );
+}
@@ -0,0 +1,124 @@
+use ekke_config :: { Config, EkkeCfgError, Validate, Validator, Violation } ;
+use std         :: { convert::TryFrom                                     } ;
+
+mod common;
+use common::*;
+
+
+impl Validate for Settings
+{
+	fn validate( &self, v: &mut Validator )
+	{
+		v.one_of   ( "/my_app/log_lvl"  , &self.my_app.log_lvl.as_str(), &[ "error", "warn", "info", "debug" ] );
+		v.non_empty( "/other_comp/primes", &self.other_comp.primes                                                 );
+		v.matches  ( "/my_app/db_path"  , &self.my_app.db_path         , r"\.sqlite$"                              );
+	}
+}
+
+
+fn violations( err: failure::Error ) -> Vec< Violation >
+{
+	match err.find_root_cause().downcast_ref::< EkkeCfgError >()
+	{
+		Some( EkkeCfgError::Invalid( violations ) ) => violations.to_vec(),
+		_                                           => panic!( "wrong error: {}", err ),
+	}
+}
+
+
+
+#[ test ] fn test_valid()
+{
+	let mut cfg = file_data().validated().unwrap();
+
+	cfg.merge_runtime( "my_app: { log_lvl: info }" ).unwrap();
+
+	assert_eq!( cfg.get().my_app.log_lvl, "info" );
+}
+
+
+#[ test ] fn test_rejected()
+{
+	let mut cfg = file_data().validated().unwrap();
+
+	let err = cfg.merge_runtime( "{ my_app: { log_lvl: loud, db_path: db.txt }, other_comp: { primes: [] } }" ).unwrap_err();
+
+	let pointers: Vec< String > = violations( err ).into_iter().map( |v| v.pointer ).collect();
+
+	assert_eq!( pointers, vec![ "/my_app/log_lvl", "/other_comp/primes", "/my_app/db_path" ] );
+
+	// The previous settings are kept.
+	//
+	assert_eq!( cfg.get().my_app.log_lvl, "warn" );
+	assert!( cfg.runtime().is_none() );
+	assert_eq!( cfg.history_len(), 0 );
+}
+
+
+#[ test ] fn test_invalid_defaults()
+{
+	let cfg: Config<Settings> = Config::try_from( "default: { my_app: { db_path: a.sqlite, log_lvl: loud }, other_comp: { primes: [ 1 ], algo: b } }" ).unwrap();
+
+	let err = cfg.validated().unwrap_err();
+
+	assert_eq!( violations( err )[0].message, r#""loud" is not one of ["error", "warn", "info", "debug"]"# );
+}
+
+
+#[ test ] fn test_transaction()
+{
+	let mut cfg = file_data().validated().unwrap();
+
+	let mut tx = cfg.transaction();
+
+	tx.merge_runtime( "my_app: { log_lvl: loud }" ).unwrap();
+	tx.merge_runtime( "my_app: { log_lvl: info }" ).unwrap();
+	tx.commit().unwrap();
+
+	let mut tx = cfg.transaction();
+
+	tx.merge_runtime( "other_comp: { primes: [] }" ).unwrap();
+	assert!( tx.commit().is_err() );
+
+	assert_eq!( cfg.get().my_app.log_lvl   , "info"                 );
+	assert_eq!( cfg.get().other_comp.primes, vec![ 1, 3, 5, 7, 11 ] );
+}
+
+
+#[ test ] fn test_cross_field()
+{
+	fn check( s: &Settings, v: &mut Validator )
+	{
+		v.check( "/my_app/log_lvl", s.my_app.log_lvl != "debug" || s.other_comp.algo != "euler", "debug is not supported with euler" );
+		v.range( "/other_comp/primes", &s.other_comp.primes.len(), ..6 );
+	}
+
+	let mut cfg = file_data();
+
+	cfg.set_validator( Some( check ) ).unwrap();
+
+	assert!( cfg.merge_runtime( "my_app: { log_lvl: debug }" ).is_err() );
+	assert!( cfg.merge_runtime( "other_comp: { algo: gauss }" ).is_ok() );
+	assert!( cfg.merge_runtime( "my_app: { log_lvl: debug }" ).is_ok() );
+
+	let err = cfg.merge_runtime( "other_comp: { primes: [ 1, 2, 3, 4, 5, 6 ] }" ).unwrap_err();
+	assert_eq!( violations( err )[0].message, "6 is not in the range ..6" );
+
+	// Turn it off.
+	//
+	cfg.set_validator( None ).unwrap();
+	assert!( cfg.merge_runtime( "other_comp: { primes: [ 1, 2, 3, 4, 5, 6 ] }" ).is_ok() );
+}
+
+
+#[ test ] fn test_sensitive()
+{
+	let mut cfg = file_data().validated().unwrap();
+
+	cfg.add_sensitive( "/my_app/db_path" );
+
+	let err = cfg.merge_runtime( "my_app: { db_path: secret.txt }" ).unwrap_err();
+
+	assert!( !err.to_string().contains( "secret.txt" ) );
+	assert_eq!( violations( err )[0].message, "*** is not valid" );
+}
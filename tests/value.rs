@@ -0,0 +1,154 @@
+use serde_yaml  :: { Mapping, Value                } ;
+use ekke_config :: { EkkeCfgError, Layer, Pointer } ;
+use serde       :: { Serialize                     } ;
+use std         :: { collections::BTreeMap         } ;
+
+mod common;
+use common::*;
+
+
+#[ derive( Serialize ) ]
+//
+struct LogLvl
+{
+	log_lvl: String,
+}
+
+
+#[ derive( Serialize ) ]
+//
+struct Update
+{
+	my_app: LogLvl,
+}
+
+
+
+#[ test ] fn test_struct()
+{
+	let mut cfg = file_data();
+
+	cfg.merge_runtime_value( Update { my_app: LogLvl { log_lvl: "info".to_string() } } ).unwrap();
+
+	assert_eq!( cfg.get().my_app.log_lvl, "info"           );
+	assert_eq!( cfg.get().my_app.db_path, "data/db.sqlite" );
+}
+
+
+#[ test ] fn test_no_injection()
+{
+	let mut cfg = file_data();
+
+	// As yaml text, this would set the algo as well.
+	//
+	let lvl = "info, algo: gauss }, other_comp: { algo: gauss";
+
+	cfg.merge_userset_at( "/my_app/log_lvl", lvl ).unwrap();
+
+	assert_eq!( cfg.get().my_app.log_lvl , lvl     );
+	assert_eq!( cfg.get().other_comp.algo, "euler" );
+}
+
+
+#[ test ] fn test_value()
+{
+	let mut cfg = file_data();
+
+	let mut data = Mapping::new();
+	let mut comp = Mapping::new();
+
+	comp.insert( "primes".into(), vec![ 2, 3 ].into() );
+	data.insert( "other_comp".into(), comp.clone().into() );
+
+	cfg.merge_runtime_value( &data ).unwrap();
+	assert_eq!( cfg.get().other_comp.primes, vec![ 2, 3 ] );
+
+	cfg.merge_layer_value( "extra", Value::Mapping( data ) ).unwrap();
+	assert_eq!( cfg.layer( "extra" ).unwrap().data().len(), 1 );
+
+	cfg.merge_runtime_at( "/other_comp", BTreeMap::from( [ ( "algo", "gauss" ) ] ) ).unwrap();
+	assert_eq!( cfg.get().other_comp.algo, "gauss" );
+}
+
+
+#[ test ] fn test_pointer()
+{
+	let mut cfg = file_data();
+
+	cfg.merge_runtime_at( "/other_comp/primes", [ 1, 2 ] ).unwrap();
+	cfg.merge_layer_at  ( "a~1b", "/my~1app/x~0y", 1     ).unwrap();
+
+	assert_eq!( cfg.get().other_comp.primes, vec![ 1, 2 ] );
+	assert_eq!( cfg.layer( "a~1b" ).unwrap().value().jptr( "/my~1app/x~0y" ).unwrap(), 1 );
+
+	let err = cfg.merge_runtime_at( "my_app", 1 ).unwrap_err();
+
+	assert!( matches!( err.find_root_cause().downcast_ref(), Some( EkkeCfgError::ConfigParse ) ) );
+}
+
+
+#[ test ] fn test_same_semantics()
+{
+	let mut cfg = file_data();
+
+	// Values have no tags, so a key that looks like one is just a key.
+	//
+	let mut append = Mapping::new();
+	append.insert( "!append".into(), vec![ 11 ].into() );
+
+	assert!( cfg.merge_runtime_at( "/other_comp/primes", append ).is_err() );
+
+	cfg.merge_runtime_at( "/other_comp/primes", vec![ 11 ] ).unwrap();
+	assert_eq!( cfg.get().other_comp.primes, vec![ 11 ] );
+
+	// A wrong type leaves the config unchanged.
+	//
+	assert!( cfg.merge_runtime_at( "/other_comp/primes", "none" ).is_err() );
+	assert_eq!( cfg.runtime().unwrap().jptr( "/other_comp/primes" ).unwrap(), &Value::from( vec![ 11 ] ) );
+
+	// Only mappings can be merged into a layer.
+	//
+	assert!( cfg.merge_layer_value( Layer::RUNTIME, vec![ 1 ] ).is_err() );
+	assert!( cfg.merge_runtime_at ( ""            , 1         ).is_err() );
+}
+
+
+#[ test ] fn test_transaction()
+{
+	let mut cfg = file_data();
+	let mut tx  = cfg.transaction();
+
+	tx.merge_layer_at   ( Layer::RUNTIME, "/my_app/log_lvl", "info"                                  ).unwrap();
+	tx.merge_layer_value( Layer::USERSET, Update { my_app: LogLvl { log_lvl: "error".to_string() } } ).unwrap();
+	tx.commit().unwrap();
+
+	assert_eq!( cfg.get().my_app.log_lvl, "info" );
+	assert_eq!( cfg.userset().unwrap().jptr( "/my_app/log_lvl" ).unwrap(), "error" );
+}
+
+
+#[ test ] fn test_literal()
+{
+	let mut cfg = file_data();
+
+	std::env::set_var( "EKKE_VALUE_SECRET", "hunter2" );
+
+	// Strings in values don't refer to other values.
+	//
+	for s in &[ "${env:EKKE_VALUE_SECRET}", "$${literal}", "price ${", "${/my_app/log_lvl}" ]
+	{
+		cfg.merge_runtime_at( "/my_app/db_path", s ).unwrap();
+		assert_eq!( cfg.get().my_app.db_path, *s );
+
+		cfg.merge_runtime_value( Update { my_app: LogLvl { log_lvl: s.to_string() } } ).unwrap();
+		assert_eq!( cfg.get().my_app.log_lvl, *s );
+	}
+
+	// Yaml text still can.
+	//
+	cfg.merge_runtime( "my_app: { db_path: '${/my_app/log_lvl}' }" ).unwrap();
+	assert_eq!( cfg.get().my_app.db_path, "${/my_app/log_lvl}" );
+
+	cfg.merge_runtime( "my_app: { log_lvl: '${env:EKKE_VALUE_SECRET}' }" ).unwrap();
+	assert_eq!( cfg.get().my_app.db_path, "hunter2" );
+}
@@ -0,0 +1,76 @@
+use ekke_config :: { Config, ConfigBuilder, Layer, XdgDirs } ;
+use std         :: { env, path::PathBuf                    } ;
+
+mod common;
+use common::*;
+
+
+const DEFAULT: &str =
+"
+my_app:
+  db_path: data/db.sqlite
+  log_lvl: debug
+
+other_comp:
+  primes: [ 1, 3, 5, 7 ]
+  algo  : fournier
+";
+
+
+fn data( dir: &str ) -> PathBuf
+{
+	PathBuf::from( env!( "CARGO_MANIFEST_DIR" ) ).join( "data/xdg" ).join( dir )
+}
+
+
+
+// Environment variables are global to the process, so everything is in one test.
+//
+#[ test ] fn test_xdg()
+{
+	let dirs = env::join_paths( vec![ data( "etc1" ), PathBuf::from( "relative/is/ignored" ), data( "etc2" ) ] ).unwrap();
+
+	env::set_var( "XDG_CONFIG_HOME", data( "home" ) );
+	env::set_var( "XDG_CONFIG_DIRS", dirs           );
+
+	let xdg = XdgDirs::new( "ekke" );
+
+	assert_eq!( xdg.user()  , Some( data( "home/ekke" ).as_path() ) );
+	assert_eq!( xdg.system(), vec![ PathBuf::from( "/etc/ekke" ), data( "etc2/ekke" ), data( "etc1/ekke" ) ] );
+
+	assert_eq!
+	(
+		xdg.find( "config.yml" ),
+
+		vec![ data( "etc2/ekke/config.yml" ), data( "etc1/ekke/config.yml" ), data( "home/ekke/config.yml" ) ]
+	);
+
+
+	let cfg: Config<Settings> = ConfigBuilder::new()
+
+		.string( Layer::DEFAULT, DEFAULT      )
+		.xdg   ( "ekke"        , "config.yml" )
+
+		.build().unwrap();
+
+	assert_eq!( cfg.get().my_app.db_path , "/var/lib/ekke/db.sqlite" );
+	assert_eq!( cfg.get().my_app.log_lvl , "error"                   );
+	assert_eq!( cfg.get().other_comp.algo, "euler"                   );
+
+	let origins: Vec<_> = cfg.layers()[ 1.. ].iter().map( |l| l.origin().unwrap().to_string() ).collect();
+
+	assert_eq!( origins.len(), 3 );
+	assert!( origins[0].ends_with( "etc2/ekke/config.yml" ) );
+	assert!( origins[1].ends_with( "etc1/ekke/config.yml" ) );
+	assert!( origins[2].ends_with( "home/ekke/config.yml" ) );
+
+	assert_eq!( cfg.userset().unwrap(), cfg.layers()[ 3 ].value() );
+
+
+	// Without XDG_CONFIG_HOME, fall back to ~/.config
+	//
+	env::remove_var( "XDG_CONFIG_HOME" );
+	env::set_var   ( "HOME", data( "home" ) );
+
+	assert_eq!( XdgDirs::new( "ekke" ).user(), Some( data( "home/.config/ekke" ).as_path() ) );
+}